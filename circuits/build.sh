@@ -0,0 +1,49 @@
+#!/usr/bin/env bash
+# Compiles the circuits and refreshes every copy of their artifacts: the ACIR JSON and VK in
+# crates/anastasia-rs/data, the Android assets and the Solidity verifiers.
+#
+# Requires nargo 1.0.0-beta.8, bb 1.0.0-nightly.20250723 and jq. Run it after any change to the
+# parameters of a `main`, since the witness order of the prover follows them.
+#
+#   ./build.sh                 # every circuit
+#   ./build.sh es256_ca ...    # only the given ones
+set -euo pipefail
+
+cd "$(dirname "$0")"
+DATA=../crates/anastasia-rs/data
+ASSETS=../android/v2/app/src/main/assets
+
+# circuit -> Android asset directory
+declare -A ANDROID=([es256_ca]=ca [es256_ee]=ee)
+# circuit -> Solidity verifier directory
+declare -A SOLIDITY=([es256_ca]=ca_verifier [es256_ee]=ee_verifier [es256_ee_long_ext]=ee_long_verifier)
+
+global() {
+    sed -n "s/^global $2: u32 = \([0-9]*\);/\1/p" "$1/src/main.nr"
+}
+
+if [ $# -eq 0 ]; then
+    set -- $(grep -l '^type = "bin"' */Nargo.toml | xargs -n1 dirname)
+fi
+
+for name in "$@"; do
+    echo "==> $name"
+    (cd "$name" && nargo compile && bb write_vk -b "target/$name.json" -o target --oracle_hash keccak)
+
+    # the extension limits are not in the ABI, so the circuit JSON carries them for `Circuit::new`
+    jq -c \
+        --argjson len "$(global "$name" MAX_EXTRA_EXT_LEN)" \
+        --argjson count "$(global "$name" MAX_EXTRA_EXT_COUNT)" \
+        '{noir_version, max_extra_extension_len: $len, max_extra_extension_count: $count} + .' \
+        "$name/target/$name.json" > "$DATA/$name.json"
+    cp "$name/target/vk" "$DATA/$name.vk"
+
+    if [ -n "${ANDROID[$name]:-}" ]; then
+        cp "$DATA/$name.json" "$DATA/$name.vk" "$ASSETS/${ANDROID[$name]}/"
+    fi
+    if [ -n "${SOLIDITY[$name]:-}" ]; then
+        bb write_solidity_verifier -k "$name/target/vk" -o "solidity_verifier/${SOLIDITY[$name]}/Verifier.sol"
+    fi
+done
+
+echo "Check that the limits in $DATA/manifest.json still match the circuits."
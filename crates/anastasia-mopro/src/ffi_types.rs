@@ -1,11 +1,19 @@
 use uniffi::Record;
 
+/// Paths of the circuit artifacts, or alternatively their (optionally gzipped) bytes, which take
+/// precedence over the paths when set.
 #[derive(Clone, Debug, Record)]
 pub struct CircuitMeta {
     pub id: String,
     pub circuit_path: String,
     pub verification_key_path: String,
     pub srs_path: String,
+    #[uniffi(default = None)]
+    pub circuit_bytes: Option<Vec<u8>>,
+    #[uniffi(default = None)]
+    pub verification_key_bytes: Option<Vec<u8>>,
+    #[uniffi(default = None)]
+    pub srs_bytes: Option<Vec<u8>>,
 }
 
 impl From<anastasia_rs::CircuitMeta> for CircuitMeta {
@@ -15,6 +23,9 @@ impl From<anastasia_rs::CircuitMeta> for CircuitMeta {
             circuit_path: meta.circuit_path,
             verification_key_path: meta.verification_key_path,
             srs_path: meta.srs_path,
+            circuit_bytes: meta.circuit_bytes,
+            verification_key_bytes: meta.verification_key_bytes,
+            srs_bytes: meta.srs_bytes,
         }
     }
 }
@@ -26,6 +37,9 @@ impl From<CircuitMeta> for anastasia_rs::CircuitMeta {
             circuit_path: meta.circuit_path,
             verification_key_path: meta.verification_key_path,
             srs_path: meta.srs_path,
+            circuit_bytes: meta.circuit_bytes,
+            verification_key_bytes: meta.verification_key_bytes,
+            srs_bytes: meta.srs_bytes,
         }
     }
 }
@@ -11,6 +11,8 @@ mopro_ffi::app!();
 
 mod ffi_types;
 
+use std::sync::Arc;
+
 use crate::ffi_types::{CircuitMeta, CommitResult, ProofResult};
 
 #[uniffi::export]
@@ -42,21 +44,100 @@ fn prove(
     prev_cmt: String,
     prev_cmt_r: String,
 ) -> Result<ProofResult, MoproError> {
+    let issuer = anastasia_rs::IssuerInputs {
+        authority_key_id,
+        pk_x: issuer_pk_x,
+        pk_y: issuer_pk_y,
+        cmt: prev_cmt,
+        cmt_r: prev_cmt_r,
+    };
     let proof = anastasia_rs::prove(
         &circuit_meta.into(),
         cert,
-        None,
-        authority_key_id,
-        issuer_pk_x,
-        issuer_pk_y,
-        prev_cmt,
-        prev_cmt_r,
+        &issuer,
+        anastasia_rs::ProveOptions::default(),
     )
     .map_err(|e| MoproError::NoirError(e.to_string()))?;
 
     Ok(proof.into())
 }
 
+/// Chooses the smallest circuit of the manifest at `manifest_path` that can prove `cert`.
+#[uniffi::export]
+fn select_circuit(manifest_path: String, cert: Vec<u8>) -> Result<CircuitMeta, MoproError> {
+    let registry = anastasia_rs::CircuitRegistry::from_manifest(manifest_path)
+        .map_err(|e| MoproError::NoirError(e.to_string()))?;
+    let descriptor = registry
+        .select_for_der(&cert, anastasia_rs::CircuitVariant::default())
+        .map_err(|e| MoproError::NoirError(e.to_string()))?;
+    Ok(descriptor.meta.clone().into())
+}
+
+/// Long-lived prover over the circuits of a manifest, which loads each circuit once and keeps
+/// the SRS set up across proofs
+#[derive(uniffi::Object)]
+pub struct Prover {
+    inner: anastasia_rs::Prover,
+}
+
+#[uniffi::export]
+impl Prover {
+    #[uniffi::constructor]
+    pub fn new(manifest_path: String) -> Result<Arc<Self>, MoproError> {
+        let registry = anastasia_rs::CircuitRegistry::from_manifest(manifest_path)
+            .map_err(|e| MoproError::NoirError(e.to_string()))?;
+        Ok(Arc::new(Prover {
+            inner: anastasia_rs::Prover::new(registry),
+        }))
+    }
+
+    /// Loads every circuit of the manifest ahead of the first proof.
+    pub fn warm_up(&self) -> Result<(), MoproError> {
+        self.inner
+            .warm_up()
+            .map_err(|e| MoproError::NoirError(e.to_string()))
+    }
+
+    pub fn select_circuit(&self, cert: Vec<u8>) -> Result<CircuitMeta, MoproError> {
+        let descriptor = self
+            .inner
+            .registry()
+            .select_for_der(&cert, anastasia_rs::CircuitVariant::default())
+            .map_err(|e| MoproError::NoirError(e.to_string()))?;
+        Ok(descriptor.meta.clone().into())
+    }
+
+    pub fn prove(
+        &self,
+        circuit_id: String,
+        cert: Vec<u8>,
+        authority_key_id: Vec<u8>,
+        issuer_pk_x: Vec<u8>,
+        issuer_pk_y: Vec<u8>,
+        prev_cmt: String,
+        prev_cmt_r: String,
+    ) -> Result<ProofResult, MoproError> {
+        let issuer = anastasia_rs::IssuerInputs {
+            authority_key_id,
+            pk_x: issuer_pk_x,
+            pk_y: issuer_pk_y,
+            cmt: prev_cmt,
+            cmt_r: prev_cmt_r,
+        };
+        let proof = self
+            .inner
+            .prove(
+                &circuit_id,
+                cert,
+                &issuer,
+                anastasia_rs::ProveOptions::default(),
+            )
+            .map_err(|e| MoproError::NoirError(e.to_string()))?;
+
+        Ok(proof.into())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,389 @@
+//! Trust anchors a certificate chain can start from.
+//!
+//! The Android Key Attestation root certificates published by Google are not bundled in the
+//! crate, so there is no `TrustAnchorStore::android_roots()` yet: embedding them needs copies
+//! checked against Google's published list (see the root certificate section of
+//! <https://developer.android.com/privacy-and-security/security-key-attestation>), and none is
+//! in this tree. Until then, download them from there and load them with
+//! [`TrustAnchorStore::load_file`] or [`TrustAnchorStore::load_dir`] from PEM or DER files.
+//!
+//! The circuits verify ES256 signatures and commit to P-256 keys only. Anchors with other key
+//! algorithms, such as the RSA and P-384 Google roots, can be listed and matched but not
+//! committed to: a chain under them must be proved from its topmost P-256 intermediate, whose
+//! commitment is then `c0`.
+
+use std::{fs, path::Path};
+
+use ark_bn254::Fr;
+use ark_ff::AdditiveGroup;
+use x509_parser::{prelude::*, public_key::PublicKey};
+
+use crate::{
+    api::{CommitResult, commit_anchor},
+    cert::SubjectAttrs,
+    merkle::MerkleTree,
+    utils::{field_to_hex, hex_to_field},
+};
+
+const PEM_MARKER: &[u8] = b"-----BEGIN ";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyAlgorithm {
+    EcP256,
+    EcP384,
+    Rsa { bits: usize },
+    Other,
+}
+
+#[derive(Debug, Clone)]
+pub struct TrustAnchor {
+    pub name: String,
+    /// Subject DN in RFC 4514 notation
+    pub subject: String,
+    pub key_algorithm: KeyAlgorithm,
+    pub der: Vec<u8>,
+}
+
+impl TrustAnchor {
+    pub fn from_der(name: &str, der: Vec<u8>) -> Result<Self, String> {
+        let (_, cert) = X509Certificate::from_der(&der)
+            .map_err(|e| format!("Failed to parse anchor `{}`: {}", name, e))?;
+
+        let key_algorithm = match cert.public_key().parsed() {
+            Ok(PublicKey::EC(ec)) if ec.key_size() == 256 => KeyAlgorithm::EcP256,
+            Ok(PublicKey::EC(ec)) if ec.key_size() == 384 => KeyAlgorithm::EcP384,
+            Ok(PublicKey::RSA(rsa)) => KeyAlgorithm::Rsa {
+                bits: rsa.key_size(),
+            },
+            _ => KeyAlgorithm::Other,
+        };
+        let subject = cert.subject().to_string();
+
+        Ok(TrustAnchor {
+            name: name.to_string(),
+            subject,
+            key_algorithm,
+            der,
+        })
+    }
+
+    /// Parses every certificate of a PEM bundle. Anchors after the first one are named
+    /// `name#1`, `name#2`, ...
+    pub fn from_pem(name: &str, pem: &[u8]) -> Result<Vec<Self>, String> {
+        let mut anchors = Vec::new();
+        for block in Pem::iter_from_buffer(pem) {
+            let block = block.map_err(|e| format!("Failed to read PEM `{}`: {}", name, e))?;
+            if block.label != "CERTIFICATE" {
+                continue;
+            }
+            // numbered among the certificates only, whatever other blocks precede them
+            let name = match anchors.len() {
+                0 => name.to_string(),
+                i => format!("{}#{}", name, i),
+            };
+            anchors.push(TrustAnchor::from_der(&name, block.contents)?);
+        }
+        if anchors.is_empty() {
+            return Err(format!("No certificate found in PEM `{}`", name));
+        }
+        Ok(anchors)
+    }
+
+    /// Subject attributes committed to by the anchor commitment.
+    pub fn commitment_input(&self) -> Result<SubjectAttrs, String> {
+        if self.key_algorithm != KeyAlgorithm::EcP256 {
+            return Err(format!(
+                "anchor `{}` has a {:?} key; only P-256 anchors can be committed to, so use \
+                 the topmost P-256 intermediate below it as the anchor",
+                self.name, self.key_algorithm
+            ));
+        }
+        SubjectAttrs::from_der(&self.der)
+    }
+
+    /// Computes the anchor commitment `c0`; see [`commit_anchor`].
+    pub fn commit(&self, r: Option<String>) -> Result<CommitResult, String> {
+        self.commitment_input()?;
+        commit_anchor(self.der.clone(), r)
+    }
+
+    fn is_issuer_of(&self, cert: &X509Certificate) -> bool {
+        match X509Certificate::from_der(&self.der) {
+            Ok((_, anchor)) => anchor.subject().as_raw() == cert.issuer().as_raw(),
+            Err(_) => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TrustAnchorStore {
+    anchors: Vec<TrustAnchor>,
+}
+
+impl TrustAnchorStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, anchor: TrustAnchor) {
+        self.anchors.push(anchor);
+    }
+
+    /// Loads the anchors of a PEM (possibly several certificates) or DER file, named after
+    /// the file stem.
+    pub fn load_file<P: AsRef<Path>>(&mut self, path: P) -> Result<(), String> {
+        let path = path.as_ref();
+        let bytes =
+            fs::read(path).map_err(|e| format!("Failed to read anchor file {:?}: {}", path, e))?;
+        let name = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .ok_or_else(|| format!("Invalid anchor file name {:?}", path))?;
+
+        // PEM files may start with explanatory text before the first block
+        if bytes.windows(PEM_MARKER.len()).any(|w| w == PEM_MARKER) {
+            self.anchors.extend(TrustAnchor::from_pem(name, &bytes)?);
+        } else {
+            self.anchors.push(TrustAnchor::from_der(name, bytes)?);
+        }
+        Ok(())
+    }
+
+    /// Loads every `.pem`, `.crt`, `.cer` and `.der` file of `dir` in file name order.
+    pub fn load_dir<P: AsRef<Path>>(&mut self, dir: P) -> Result<(), String> {
+        let dir = dir.as_ref();
+        let mut paths = fs::read_dir(dir)
+            .map_err(|e| format!("Failed to read anchor directory {:?}: {}", dir, e))?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| {
+                path.extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| matches!(ext, "pem" | "crt" | "cer" | "der"))
+            })
+            .collect::<Vec<_>>();
+        paths.sort();
+        for path in paths {
+            self.load_file(path)?;
+        }
+        Ok(())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &TrustAnchor> {
+        self.anchors.iter()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&TrustAnchor> {
+        self.anchors.iter().find(|anchor| anchor.name == name)
+    }
+
+    /// Builds the [`AnchorSet`] of every anchor in the store.
+    pub fn anchor_set(&self, depth: usize) -> Result<AnchorSet, String> {
+        AnchorSet::new(self.anchors.iter(), depth)
+    }
+
+    /// Returns the anchor whose subject is the issuer of `cert`.
+    pub fn find_issuer(&self, cert: &[u8]) -> Result<Option<&TrustAnchor>, String> {
+        let (_, cert) = X509Certificate::from_der(cert)
+            .map_err(|e| format!("Failed to parse certificate: {}", e))?;
+        Ok(self
+            .anchors
+            .iter()
+            .find(|anchor| anchor.is_issuer_of(&cert)))
+    }
+}
+
+/// Poseidon Merkle tree of anchor commitments, with which the first proof of a chain shows that
+/// it starts from one of several anchors without disclosing which.
+///
+/// The leaves are the anchor commitments with the randomness 0, so that each anchor has a fixed
+/// leaf. Only the root becomes a public input.
+pub struct AnchorSet {
+    cmts: Vec<Fr>,
+    tree: MerkleTree,
+}
+
+impl AnchorSet {
+    /// Builds the set of `anchors` with `depth` levels, matching the `anchor_path` length of the
+    /// anchor-set circuits.
+    pub fn new<'a>(
+        anchors: impl IntoIterator<Item = &'a TrustAnchor>,
+        depth: usize,
+    ) -> Result<Self, String> {
+        let mut cmts = Vec::new();
+        for anchor in anchors {
+            let cmt = hex_to_field(&anchor.commit(Some("00".to_string()))?.cmt)?;
+            if !cmts.contains(&cmt) {
+                cmts.push(cmt);
+            }
+        }
+        if cmts.is_empty() {
+            return Err("anchor set must contain at least one anchor".to_string());
+        }
+        let tree = MerkleTree::new(cmts.clone(), Fr::ZERO, depth)
+            .map_err(|e| format!("{} anchors do not fit: {}", cmts.len(), e))?;
+        Ok(AnchorSet { cmts, tree })
+    }
+
+    pub fn root(&self) -> Fr {
+        self.tree.root()
+    }
+
+    pub fn depth(&self) -> usize {
+        self.tree.depth()
+    }
+
+    pub fn len(&self) -> usize {
+        self.cmts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cmts.is_empty()
+    }
+
+    /// Index and Merkle path of the anchor with the commitment `anchor_cmt` (with the
+    /// randomness 0), failing if it is not in the set.
+    pub(crate) fn membership_witness(&self, anchor_cmt: Fr) -> Result<(u64, Vec<Fr>), String> {
+        let index = self
+            .cmts
+            .iter()
+            .position(|cmt| *cmt == anchor_cmt)
+            .ok_or("anchor is not in the anchor set")?;
+        Ok((index as u64, self.tree.path(index)?))
+    }
+}
+
+impl std::fmt::Debug for AnchorSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AnchorSet")
+            .field("anchors", &self.cmts.len())
+            .field("root", &field_to_hex(&self.root()))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::{Engine as _, engine::general_purpose::STANDARD};
+
+    fn to_pem(der: &[u8]) -> String {
+        let b64 = STANDARD.encode(der);
+        let lines = b64
+            .as_bytes()
+            .chunks(64)
+            .map(|line| std::str::from_utf8(line).unwrap())
+            .collect::<Vec<_>>()
+            .join("\n");
+        format!(
+            "-----BEGIN CERTIFICATE-----\n{}\n-----END CERTIFICATE-----\n",
+            lines
+        )
+    }
+
+    #[test]
+    fn test_trust_anchor_from_der() {
+        let der = std::fs::read("test_data/es256_ca.der").unwrap();
+        let anchor = TrustAnchor::from_der("strongbox", der).unwrap();
+        assert_eq!(anchor.name, "strongbox");
+        assert_eq!(anchor.key_algorithm, KeyAlgorithm::EcP256);
+        assert_eq!(
+            anchor.subject,
+            "CN=e5bfa97715c1cb1170c30e01331eef42, O=StrongBox"
+        );
+
+        let CommitResult { cmt, .. } = anchor.commit(Some("feedface".to_string())).unwrap();
+        assert_eq!(
+            cmt,
+            "2a296b0c9a2c8b4c6c56357c632860849f42a4defa6b491b2421a962a3543f5c"
+        );
+    }
+
+    #[test]
+    fn test_trust_anchor_from_pem() {
+        let ca = std::fs::read("test_data/es256_ca.der").unwrap();
+        let ee = std::fs::read("test_data/es256_ee.der").unwrap();
+        let pem = format!("{}{}", to_pem(&ca), to_pem(&ee));
+
+        let anchors = TrustAnchor::from_pem("bundle", pem.as_bytes()).unwrap();
+        assert_eq!(anchors.len(), 2);
+        assert_eq!(anchors[0].name, "bundle");
+        assert_eq!(anchors[0].der, ca);
+        assert_eq!(anchors[1].name, "bundle#1");
+        assert_eq!(anchors[1].der, ee);
+
+        assert!(TrustAnchor::from_pem("empty", b"").is_err());
+
+        // blocks other than certificates do not shift the numbering
+        let pem = format!(
+            "-----BEGIN PUBLIC KEY-----\nAAAA\n-----END PUBLIC KEY-----\n{}{}",
+            to_pem(&ca),
+            to_pem(&ee)
+        );
+        let anchors = TrustAnchor::from_pem("bundle", pem.as_bytes()).unwrap();
+        assert_eq!(anchors[0].name, "bundle");
+        assert_eq!(anchors[1].name, "bundle#1");
+    }
+
+    #[test]
+    fn test_load_pem_with_leading_text() {
+        let ca = std::fs::read("test_data/es256_ca.der").unwrap();
+        let path =
+            std::env::temp_dir().join(format!("anastasia-anchor-{}.pem", std::process::id()));
+        std::fs::write(&path, format!("# StrongBox CA\n\n{}", to_pem(&ca))).unwrap();
+
+        let mut store = TrustAnchorStore::new();
+        let loaded = store.load_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        loaded.unwrap();
+        assert_eq!(store.iter().next().unwrap().der, ca);
+    }
+
+    #[test]
+    fn test_find_issuer() {
+        let mut store = TrustAnchorStore::new();
+        store.load_file("test_data/es256_ca.der").unwrap();
+        assert!(store.get("es256_ca").is_some());
+
+        let ee = std::fs::read("test_data/es256_ee.der").unwrap();
+        let issuer = store.find_issuer(&ee).unwrap().unwrap();
+        assert_eq!(issuer.name, "es256_ca");
+
+        let ca = std::fs::read("test_data/es256_ca.der").unwrap();
+        assert!(store.find_issuer(&ca).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_anchor_set() {
+        let mut store = TrustAnchorStore::new();
+        store.load_file("test_data/es256_ca.der").unwrap();
+        store.load_file("test_data/test_root.der").unwrap();
+        let set = store.anchor_set(2).unwrap();
+        assert_eq!(set.len(), 2);
+
+        // the leaf of an anchor is its commitment with the randomness 0
+        let CommitResult { cmt, .. } = store
+            .get("es256_ca")
+            .unwrap()
+            .commit(Some("00".to_string()))
+            .unwrap();
+        let cmt = hex_to_field(&cmt).unwrap();
+        let (index, path) = set.membership_witness(cmt).unwrap();
+        assert_eq!(index, 0);
+        assert_eq!(
+            crate::merkle::root_from_path(cmt, index, &path).unwrap(),
+            set.root()
+        );
+
+        let other = store.get("es256_ca").unwrap().commit(None).unwrap().cmt;
+        assert!(
+            set.membership_witness(hex_to_field(&other).unwrap())
+                .is_err()
+        );
+
+        // adding an anchor changes the root
+        let one = AnchorSet::new(store.get("es256_ca"), 2).unwrap();
+        assert_ne!(one.root(), set.root());
+        assert!(AnchorSet::new([], 2).is_err());
+        assert!(store.anchor_set(0).is_err());
+    }
+}
@@ -1,11 +1,19 @@
 use ark_bn254::Fr;
-use ark_ff::{PrimeField, UniformRand};
+use ark_ff::{AdditiveGroup, PrimeField, UniformRand};
 use ark_std::rand::rngs::OsRng;
 use chrono::{DateTime, Utc};
+use std::{fmt, sync::Arc};
 
 use crate::{
+    anchors::AnchorSet,
+    cert::{ParsedCert, SubjectAttrs},
     circuit::{Circuit, CircuitMeta},
-    utils,
+    predicate::Predicate,
+    prove::WitnessOptions,
+    public_inputs::{AttestationDisclosure, Nullifier, Nym, Possession, split_proof_with_layout},
+    registry::{CircuitRegistry, CircuitRole, CircuitVariant},
+    revocation::RevocationTree,
+    utils::{self, TimeGranularity},
 };
 
 pub struct CommitResult {
@@ -20,13 +28,7 @@ pub fn commit_attrs(
     subject_pk_y: Vec<u8>,
     r: Option<String>,
 ) -> Result<CommitResult, String> {
-    let mut rng = OsRng;
-    let r = if let Some(r_hex) = r {
-        let r_bytes = hex::decode(&r_hex).map_err(|e| format!("failed to decode r: {}", e))?;
-        Fr::from_be_bytes_mod_order(&r_bytes)
-    } else {
-        Fr::rand(&mut rng)
-    };
+    let r = randomness_or_rand(r)?;
 
     let cmt = utils::commit_attrs(
         {
@@ -59,34 +61,323 @@ pub fn commit_attrs(
     })
 }
 
+/// Computes the commitment to the subject attributes of `cert`, i.e. the `next_cmt`
+/// a proof for `cert` would produce with the randomness `r`.
+pub fn commit_cert(cert: Vec<u8>, r: Option<String>) -> Result<CommitResult, String> {
+    let parsed_cert =
+        ParsedCert::from_der(&cert).map_err(|e| format!("Failed to parse cert: {}", e))?;
+    commit_subject_attrs(&parsed_cert.subject_attrs(), r)
+}
+
+/// Computes the anchor commitment `c0` of a trust-anchor certificate, i.e. the `prev_cmt`
+/// the first proof of a chain issued by `cert` starts from.
+///
+/// The circuits verify ES256 signatures only, so `cert` must be a CA with a P-256 key. The
+/// Google attestation roots have RSA or P-384 keys and cannot anchor a chain themselves: `c0`
+/// must come from the topmost P-256 intermediate, and the chain given to [`prove_chain`] must
+/// end with it.
+pub fn commit_anchor(cert: Vec<u8>, r: Option<String>) -> Result<CommitResult, String> {
+    let attrs = SubjectAttrs::from_der(&cert).map_err(|e| {
+        format!(
+            "Failed to parse anchor cert: {}; anchors must be P-256 CAs, such as the topmost \
+             P-256 intermediate below an RSA or P-384 root",
+            e
+        )
+    })?;
+    if !attrs.is_ca {
+        return Err("anchor certificate must be a CA".to_string());
+    }
+    if attrs.subject_key_identifier == [0u8; 20] {
+        return Err("anchor certificate must have a SubjectKeyIdentifier".to_string());
+    }
+    commit_subject_attrs(&attrs, r)
+}
+
+fn commit_subject_attrs(attrs: &SubjectAttrs, r: Option<String>) -> Result<CommitResult, String> {
+    let r = randomness_or_rand(r)?;
+    let cmt = utils::commit_attrs(
+        attrs.subject,
+        attrs.subject_key_identifier,
+        attrs.subject_pk_x,
+        attrs.subject_pk_y,
+        r,
+    )?;
+    Ok(CommitResult {
+        cmt: utils::field_to_hex(&cmt),
+        r: utils::field_to_hex(&r),
+    })
+}
+
+fn randomness_or_rand(r: Option<String>) -> Result<Fr, String> {
+    match r {
+        Some(r_hex) => {
+            let r_bytes = hex::decode(&r_hex).map_err(|e| format!("failed to decode r: {}", e))?;
+            Ok(Fr::from_be_bytes_mod_order(&r_bytes))
+        }
+        None => Ok(Fr::rand(&mut OsRng)),
+    }
+}
+
+/// Hash identifying an attesting app by its package name and the SHA-256 digest of its signing
+/// certificate, as disclosed by attested end-entity proofs.
+pub fn app_id_hash(package_name: Vec<u8>, signature_digest: Vec<u8>) -> Result<String, String> {
+    Ok(utils::field_to_hex(&utils::app_id_hash(
+        &package_name,
+        &signature_digest,
+    )?))
+}
+
+/// What a verifier asks an attested end-entity proof to show about its key attestation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AttestationRequest {
+    /// Expected attestationChallenge
+    pub nonce: Vec<u8>,
+    pub predicates: Vec<Predicate>,
+    /// Whether to disclose the [`app_id_hash`] of the attesting app
+    pub disclose_app_id: bool,
+    /// Hex app id hashes, one of which the attesting app must match
+    pub app_id_allow_list: Vec<String>,
+}
+
+impl AttestationRequest {
+    pub(crate) fn app_id_allow_list(&self) -> Result<Vec<Fr>, String> {
+        if self.app_id_allow_list.len() > AttestationDisclosure::MAX_ALLOWED_APPS {
+            return Err(format!(
+                "{} allowed apps exceed the maximum of {}",
+                self.app_id_allow_list.len(),
+                AttestationDisclosure::MAX_ALLOWED_APPS
+            ));
+        }
+        self.app_id_allow_list
+            .iter()
+            .map(|hash| match utils::hex_to_field(hash)? {
+                hash if hash == Fr::ZERO => Err("app id allow-list must not contain 0".to_string()),
+                hash => Ok(hash),
+            })
+            .collect()
+    }
+
+    /// Checks that an attestation disclosure answers this request.
+    fn check(&self, attestation: &AttestationDisclosure) -> Result<(), String> {
+        if attestation.challenge != self.nonce {
+            return Err("attestation challenge does not match the nonce".to_string());
+        }
+        if !attestation
+            .predicates
+            .iter()
+            .map(|result| result.predicate)
+            .eq(self.predicates.iter().copied())
+        {
+            return Err("proved predicates do not match the request".to_string());
+        }
+        if attestation.app_id_hash.is_some() != self.disclose_app_id {
+            return Err("app id disclosure does not match the request".to_string());
+        }
+        if attestation.app_id_allow_list != self.app_id_allow_list()? {
+            return Err("app id allow-list does not match the request".to_string());
+        }
+        Ok(())
+    }
+}
+
 pub struct ProofResult {
     pub proof: Vec<u8>,
     pub next_cmt: String,
     pub next_cmt_r: String,
 }
 
+/// The issuer of the certificate to prove, and the commitment to it the proof starts from
+pub struct IssuerInputs {
+    pub authority_key_id: Vec<u8>,
+    pub pk_x: Vec<u8>,
+    pub pk_y: Vec<u8>,
+    pub cmt: String,
+    pub cmt_r: String,
+}
+
+impl IssuerInputs {
+    /// The issuer with the subject attributes `issuer`, committed to as `cmt`
+    pub fn new(issuer: &SubjectAttrs, cmt: CommitResult) -> Self {
+        IssuerInputs {
+            authority_key_id: issuer.subject_key_identifier.to_vec(),
+            pk_x: issuer.subject_pk_x.to_vec(),
+            pk_y: issuer.subject_pk_y.to_vec(),
+            cmt: cmt.cmt,
+            cmt_r: cmt.r,
+        }
+    }
+}
+
+/// Optional inputs of a proof
+#[derive(Clone, Default)]
+pub struct ProveOptions<'a> {
+    /// The current time if not set
+    pub now: Option<DateTime<Utc>>,
+    pub now_granularity: TimeGranularity,
+    /// Proves non-revocation against the tree, with a circuit proving it
+    pub revocation: Option<&'a RevocationTree>,
+    /// Proved over the key attestation of an end-entity certificate, with a circuit disclosing
+    /// attestation properties
+    pub attestation: Option<AttestationRequest>,
+}
+
+impl ProveOptions<'_> {
+    fn witness_options(&self) -> WitnessOptions<'_> {
+        WitnessOptions {
+            now: self.now,
+            now_granularity: self.now_granularity,
+            attestation: self.attestation.as_ref(),
+            revocation: self.revocation,
+            ..Default::default()
+        }
+    }
+}
+
 pub fn prove(
     circuit_meta: &CircuitMeta,
     cert: Vec<u8>,
-    now: Option<DateTime<Utc>>,
-    authority_key_id: Vec<u8>,
-    issuer_pk_x: Vec<u8>,
-    issuer_pk_y: Vec<u8>,
-    prev_cmt: String,
-    prev_cmt_r: String,
+    issuer: &IssuerInputs,
+    options: ProveOptions,
+) -> Result<ProofResult, String> {
+    prove_with_circuit(&Circuit::new(circuit_meta)?, cert, issuer, options)
+}
+
+pub(crate) fn prove_with_circuit(
+    circuit: &Circuit,
+    cert: Vec<u8>,
+    issuer: &IssuerInputs,
+    options: ProveOptions,
 ) -> Result<ProofResult, String> {
+    let (proof, next_cmt, next_cmt_r) =
+        crate::prove::prove(circuit, cert, issuer, options.witness_options())?;
+
+    Ok(ProofResult {
+        proof,
+        next_cmt,
+        next_cmt_r,
+    })
+}
+
+pub struct NymProofResult {
+    pub proof: Vec<u8>,
+    pub next_cmt: String,
+    pub next_cmt_r: String,
+    pub nym: String,
+}
+
+/// Like [`prove`], for an end-entity circuit that also proves the context-specific pseudonym
+/// `nym` = f(subject public key, `ctx`, `user_secret`). `ctx` and `user_secret` are hex field
+/// elements. A key always yields the same `nym` for the same `ctx` and `user_secret`, while nyms
+/// for different `ctx` cannot be linked without `user_secret`.
+pub fn prove_ee_with_nym(
+    circuit_meta: &CircuitMeta,
+    cert: Vec<u8>,
+    issuer: &IssuerInputs,
+    options: ProveOptions,
+    ctx: String,
+    user_secret: String,
+) -> Result<NymProofResult, String> {
     let circuit = Circuit::new(circuit_meta)?;
+    let ctx = utils::hex_to_field(&ctx)?;
+    let user_secret = utils::hex_to_field(&user_secret)?;
 
     let (proof, next_cmt, next_cmt_r) = crate::prove::prove(
         &circuit,
         cert,
-        now,
-        authority_key_id,
-        issuer_pk_x,
-        issuer_pk_y,
-        prev_cmt,
-        prev_cmt_r,
-        circuit.max_extra_extension_len,
+        issuer,
+        WitnessOptions {
+            nym: Some((ctx, user_secret)),
+            ..options.witness_options()
+        },
+    )?;
+    let (public_inputs, _) = split_proof_with_layout(&proof, circuit.public_inputs_layout)?;
+    let nym = public_inputs
+        .nym
+        .ok_or("Circuit does not compute a nym")?
+        .nym;
+
+    Ok(NymProofResult {
+        proof,
+        next_cmt,
+        next_cmt_r,
+        nym: utils::field_to_hex(&nym),
+    })
+}
+
+pub struct NullifierProofResult {
+    pub proof: Vec<u8>,
+    pub next_cmt: String,
+    pub next_cmt_r: String,
+    pub nullifier: String,
+}
+
+/// Like [`prove`], for an end-entity circuit that also proves the nullifier
+/// f(subject public key, `scope`, `epoch`, `secret`). `scope` and `secret` are hex field elements.
+/// `secret` keeps the nullifiers of a key from being linked by anyone who knows its public key.
+/// The circuit cannot tell whether `secret` was changed, so a device yields one nullifier per
+/// `scope` and `epoch` only as long as the app keeps `secret` fixed for its attested key.
+pub fn prove_ee_with_nullifier(
+    circuit_meta: &CircuitMeta,
+    cert: Vec<u8>,
+    issuer: &IssuerInputs,
+    options: ProveOptions,
+    scope: String,
+    epoch: u64,
+    secret: String,
+) -> Result<NullifierProofResult, String> {
+    let circuit = Circuit::new(circuit_meta)?;
+    let scope = utils::hex_to_field(&scope)?;
+    let secret = utils::hex_to_field(&secret)?;
+
+    let (proof, next_cmt, next_cmt_r) = crate::prove::prove(
+        &circuit,
+        cert,
+        issuer,
+        WitnessOptions {
+            nullifier: Some((scope, epoch, secret)),
+            ..options.witness_options()
+        },
+    )?;
+    let (public_inputs, _) = split_proof_with_layout(&proof, circuit.public_inputs_layout)?;
+    let nullifier = public_inputs
+        .nullifier
+        .ok_or("Circuit does not compute a nullifier")?
+        .nullifier;
+
+    Ok(NullifierProofResult {
+        proof,
+        next_cmt,
+        next_cmt_r,
+        nullifier: utils::field_to_hex(&nullifier),
+    })
+}
+
+/// Like [`prove`], for an end-entity circuit that also proves possession of the attested key:
+/// `signature` is the DER ECDSA signature of the verifier's `challenge` (32 bytes), made on the
+/// device with the attested key using SHA256withECDSA. The key itself stays hidden.
+pub fn prove_ee_with_possession(
+    circuit_meta: &CircuitMeta,
+    cert: Vec<u8>,
+    issuer: &IssuerInputs,
+    options: ProveOptions,
+    challenge: Vec<u8>,
+    signature: Vec<u8>,
+) -> Result<ProofResult, String> {
+    let circuit = Circuit::new(circuit_meta)?;
+    let possession = Possession {
+        challenge: challenge
+            .try_into()
+            .map_err(|_| format!("challenge must be {} bytes", Possession::CHALLENGE_LEN))?,
+    };
+
+    let (proof, next_cmt, next_cmt_r) = crate::prove::prove(
+        &circuit,
+        cert,
+        issuer,
+        WitnessOptions {
+            possession: Some((&possession, &signature)),
+            ..options.witness_options()
+        },
     )?;
 
     Ok(ProofResult {
@@ -96,9 +387,594 @@ pub fn prove(
     })
 }
 
+pub struct LinkProof {
+    pub circuit_id: String,
+    pub proof: Vec<u8>,
+    pub next_cmt: String,
+    pub next_cmt_r: String,
+}
+
+pub struct ChainProofResult {
+    pub anchor_cmt: String,
+    pub anchor_cmt_r: String,
+    pub proofs: Vec<LinkProof>,
+}
+
+/// Proves every certificate of `chain` except the last one, which is taken as the trust anchor.
+///
+/// `chain` is ordered from the end-entity certificate to the anchor, as returned by Android
+/// KeyStore. The returned proofs are ordered from the anchor down to the end entity, so that
+/// `proofs[0]` starts from `anchor_cmt` and each proof starts from the previous `next_cmt`.
+/// `options.attestation` is proved over the key attestation of the end-entity certificate, with a
+/// circuit disclosing attestation properties. With a
+/// coarse `now_granularity`, every link discloses only the start of the period containing `now`.
+/// With `revocation`, every link is proved with a circuit proving non-revocation and discloses
+/// the root of the tree.
+/// With `anchor_set`, the first proof shows only that the anchor is a member of the set:
+/// `anchor_cmt` is then the root of the set and `anchor_cmt_r` is empty. No anchor-set circuit
+/// proves non-revocation, so `anchor_set` cannot be combined with `revocation`.
+pub fn prove_chain(
+    registry: &CircuitRegistry,
+    chain: Vec<Vec<u8>>,
+    anchor_set: Option<&AnchorSet>,
+    options: ProveOptions,
+) -> Result<ChainProofResult, String> {
+    prove_chain_with(
+        |cert, variant| registry.select(cert, variant).map(Arc::new),
+        chain,
+        anchor_set,
+        options,
+    )
+}
+
+/// [`prove_chain`] with the circuit of each certificate and the variant it needs chosen by
+/// `select`
+pub(crate) fn prove_chain_with(
+    mut select: impl FnMut(&ParsedCert, CircuitVariant) -> Result<Arc<Circuit>, String>,
+    chain: Vec<Vec<u8>>,
+    anchor_set: Option<&AnchorSet>,
+    options: ProveOptions,
+) -> Result<ChainProofResult, String> {
+    if chain.len() < 2 {
+        return Err("certificate chain must contain at least two certificates".to_string());
+    }
+    if anchor_set.is_some() && options.revocation.is_some() {
+        return Err(
+            "an anchor set cannot be combined with revocation, since no anchor-set circuit proves non-revocation"
+                .to_string(),
+        );
+    }
+    let (anchor, certs) = chain.split_last().unwrap();
+    let parsed_certs = certs
+        .iter()
+        .enumerate()
+        .map(|(i, der)| {
+            ParsedCert::from_der(der).map_err(|e| format!("Failed to parse chain[{}]: {}", i, e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let anchor_attrs = SubjectAttrs::from_der(anchor)
+        .map_err(|e| format!("Failed to parse chain[{}]: {}", certs.len(), e))?;
+    check_path_len(&parsed_certs, &anchor_attrs)?;
+
+    // all proofs in a chain must share the same `now`
+    let now = options.now.unwrap_or_else(Utc::now);
+
+    let CommitResult {
+        cmt: anchor_cmt,
+        r: anchor_cmt_r,
+    } = match anchor_set {
+        // the anchor-set circuit opens the anchor commitment with the randomness 0 itself
+        Some(anchor_set) => CommitResult {
+            cmt: utils::field_to_hex(&anchor_set.root()),
+            r: String::new(),
+        },
+        None => commit_anchor(anchor.clone(), None)?,
+    };
+
+    let mut prev = CommitResult {
+        cmt: anchor_cmt.clone(),
+        r: match anchor_set {
+            Some(_) => utils::field_to_hex(&Fr::ZERO),
+            None => anchor_cmt_r.clone(),
+        },
+    };
+    let mut proofs = Vec::with_capacity(certs.len());
+    for i in (0..certs.len()).rev() {
+        let cert = &parsed_certs[i];
+        let issuer = match parsed_certs.get(i + 1) {
+            Some(issuer) => issuer.subject_attrs(),
+            None => anchor_attrs,
+        };
+        check_issued_by(cert, &issuer).map_err(|e| format!("chain[{}]: {}", i, e))?;
+
+        let variant = CircuitVariant {
+            anchor_set: anchor_set.is_some() && i == certs.len() - 1,
+            revocation: options.revocation.is_some(),
+            attestation: options.attestation.is_some() && i == 0,
+        };
+        let circuit = select(cert, variant).map_err(|e| format!("chain[{}]: {}", i, e))?;
+        let (proof, next_cmt, next_cmt_r) = crate::prove::prove(
+            &circuit,
+            chain[i].clone(),
+            &IssuerInputs::new(&issuer, prev),
+            WitnessOptions {
+                now: Some(now),
+                attestation: options.attestation.as_ref().filter(|_| i == 0),
+                anchor_set: anchor_set.filter(|_| i == certs.len() - 1),
+                ..options.witness_options()
+            },
+        )
+        .map_err(|e| format!("chain[{}]: {}", i, e))?;
+
+        prev = CommitResult {
+            cmt: next_cmt.clone(),
+            r: next_cmt_r.clone(),
+        };
+        proofs.push(LinkProof {
+            circuit_id: circuit.id.clone(),
+            proof,
+            next_cmt,
+            next_cmt_r,
+        });
+    }
+
+    Ok(ChainProofResult {
+        anchor_cmt,
+        anchor_cmt_r,
+        proofs,
+    })
+}
+
+// The issuer commitment opened inside the circuit is built from the issuer name and
+// key identifier of `cert`, so they must match the subject attributes of `issuer`.
+fn check_issued_by(cert: &ParsedCert, issuer: &SubjectAttrs) -> Result<(), String> {
+    if cert.issuer_len != issuer.subject_len || cert.issuer != issuer.subject {
+        return Err("issuer does not match the subject of the next certificate".to_string());
+    }
+    if issuer.subject_key_identifier == [0u8; 20] {
+        return Err("issuing certificate has no SubjectKeyIdentifier".to_string());
+    }
+    if cert.authority_key_identifier_index != 0
+        && cert.authority_key_identifier != issuer.subject_key_identifier
+    {
+        return Err(
+            "AuthorityKeyIdentifier does not match the SubjectKeyIdentifier of the next certificate"
+                .to_string(),
+        );
+    }
+    Ok(())
+}
+
+// RFC 5280, 4.2.1.9: pathLenConstraint is the maximum number of non-self-issued
+// intermediate CA certificates that may follow the certificate in the path.
+fn check_path_len(parsed_certs: &[ParsedCert], anchor: &SubjectAttrs) -> Result<(), String> {
+    for j in 1..=parsed_certs.len() {
+        let path_len = match parsed_certs.get(j) {
+            Some(issuer) => issuer.basic_constraints_path_len,
+            None => anchor.path_len,
+        };
+        let Some(path_len) = path_len else {
+            continue;
+        };
+        let intermediates = parsed_certs[..j]
+            .iter()
+            .filter(|cert| {
+                cert.basic_constraints_ca
+                    && (cert.issuer_len != cert.subject_len || cert.issuer != cert.subject)
+            })
+            .count();
+        if intermediates > path_len as usize {
+            return Err(format!(
+                "chain[{}]: pathLenConstraint of {} exceeded by {} intermediate CA certificates",
+                j, path_len, intermediates
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// The part of a [`LinkProof`] that is sent to the verifier.
+pub struct ChainLink {
+    pub circuit_id: String,
+    pub proof: Vec<u8>,
+}
+
+impl From<&LinkProof> for ChainLink {
+    fn from(link: &LinkProof) -> Self {
+        ChainLink {
+            circuit_id: link.circuit_id.clone(),
+            proof: link.proof.clone(),
+        }
+    }
+}
+
+pub struct VerifiedChain {
+    pub now: DateTime<Utc>,
+    pub now_granularity: TimeGranularity,
+    /// Whether the chain starts from a member of the anchor set with the root `anchor_cmt`
+    /// rather than from the anchor with the commitment `anchor_cmt`
+    pub anchor_set: bool,
+    /// `next_cmt` of each link, the last one being the end-entity commitment
+    pub cmts: Vec<String>,
+    /// Root of the revocation tree no certificate of the chain is revoked in, if the circuits
+    /// prove non-revocation; verifiers must check that it is the root of a current status list
+    pub revocation_root: Option<String>,
+    /// Attestation properties disclosed by the last link, if its circuit discloses them
+    pub attestation: Option<AttestationDisclosure>,
+    /// Pseudonym of the end-entity key, if the circuit of the last link computes one
+    pub nym: Option<Nym>,
+    /// Nullifier of the end-entity key, if the circuit of the last link computes one
+    pub nullifier: Option<Nullifier>,
+    /// Challenge signed by the end-entity key, if the circuit of the last link proves possession;
+    /// verifiers must check that it is the challenge they issued for this session
+    pub possession: Option<Possession>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChainVerificationError {
+    EmptyChain,
+    InvalidAnchor(String),
+    UnknownCircuit {
+        link: usize,
+        circuit_id: String,
+    },
+    InvalidProof {
+        link: usize,
+        reason: String,
+    },
+    /// `prev_cmt` of the first link is not the anchor commitment
+    AnchorMismatch,
+    /// `prev_cmt` of `link` is not `next_cmt` of the previous link
+    CommitmentMismatch {
+        link: usize,
+    },
+    /// `now` or `now_granularity` of `link` differs from that of the first link
+    TimeMismatch {
+        link: usize,
+    },
+    /// `revocation_root` of `link` differs from that of the first link
+    RevocationMismatch {
+        link: usize,
+    },
+    /// The circuit of `link` does not prove the role its position requires: a CA certificate for
+    /// every link but the last, and an end-entity certificate for the last
+    RoleMismatch {
+        link: usize,
+        circuit_id: String,
+        expected: CircuitRole,
+    },
+    /// The pathLenConstraint of `link` allows fewer CA certificates below it than the chain has
+    PathLenExceeded {
+        link: usize,
+        path_len: u8,
+    },
+    /// The attestation disclosure of the last link does not answer the verifier's request
+    RequestMismatch {
+        link: usize,
+        reason: String,
+    },
+}
+
+impl ChainVerificationError {
+    /// Index of the failing link, if the failure is attributable to one.
+    pub fn link(&self) -> Option<usize> {
+        match self {
+            ChainVerificationError::EmptyChain | ChainVerificationError::InvalidAnchor(_) => None,
+            ChainVerificationError::AnchorMismatch => Some(0),
+            ChainVerificationError::UnknownCircuit { link, .. }
+            | ChainVerificationError::InvalidProof { link, .. }
+            | ChainVerificationError::CommitmentMismatch { link }
+            | ChainVerificationError::TimeMismatch { link }
+            | ChainVerificationError::RevocationMismatch { link }
+            | ChainVerificationError::RoleMismatch { link, .. }
+            | ChainVerificationError::PathLenExceeded { link, .. }
+            | ChainVerificationError::RequestMismatch { link, .. } => Some(*link),
+        }
+    }
+}
+
+impl fmt::Display for ChainVerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChainVerificationError::EmptyChain => write!(f, "chain contains no proofs"),
+            ChainVerificationError::InvalidAnchor(e) => {
+                write!(f, "invalid anchor commitment: {}", e)
+            }
+            ChainVerificationError::UnknownCircuit { link, circuit_id } => {
+                write!(f, "link {}: unknown circuit `{}`", link, circuit_id)
+            }
+            ChainVerificationError::InvalidProof { link, reason } => {
+                write!(f, "link {}: {}", link, reason)
+            }
+            ChainVerificationError::AnchorMismatch => {
+                write!(f, "link 0: prev_cmt does not match the anchor commitment")
+            }
+            ChainVerificationError::CommitmentMismatch { link } => write!(
+                f,
+                "link {}: prev_cmt does not match next_cmt of link {}",
+                link,
+                link - 1
+            ),
+            ChainVerificationError::TimeMismatch { link } => {
+                write!(f, "link {}: now differs from the first link", link)
+            }
+            ChainVerificationError::RevocationMismatch { link } => {
+                write!(
+                    f,
+                    "link {}: revocation root differs from the first link",
+                    link
+                )
+            }
+            ChainVerificationError::RoleMismatch {
+                link,
+                circuit_id,
+                expected,
+            } => write!(
+                f,
+                "link {}: circuit `{}` does not prove {} certificate",
+                link,
+                circuit_id,
+                match expected {
+                    CircuitRole::Ca => "a CA",
+                    CircuitRole::Ee => "an end-entity",
+                }
+            ),
+            ChainVerificationError::PathLenExceeded { link, path_len } => write!(
+                f,
+                "link {}: pathLenConstraint of {} exceeded by the CA certificates below it",
+                link, path_len
+            ),
+            ChainVerificationError::RequestMismatch { link, reason } => {
+                write!(f, "link {}: {}", link, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ChainVerificationError {}
+
+/// Verifies proofs produced by [`prove_chain`], ordered from the anchor down to the end entity.
+///
+/// Besides each proof itself, this checks that the first `prev_cmt` is `anchor_cmt`, that each
+/// following `prev_cmt` is the previous `next_cmt`, and that all proofs share the same `now`,
+/// `now_granularity` and `revocation_root`. The last link must be proved with an end-entity
+/// circuit of the registry and every other link with a CA circuit, whose disclosed
+/// pathLenConstraint must allow the CA links below it;
+/// since the proofs do not tell whether a certificate is self-issued, all of them count.
+/// The pathLenConstraint of the anchor itself is up to the caller, who knows the anchor.
+/// If the first proof shows membership in an anchor set, `anchor_cmt` is the root of the set.
+/// If the last proof discloses attestation properties, they must answer `attestation`.
+pub fn verify_chain(
+    registry: &CircuitRegistry,
+    anchor_cmt: &str,
+    proofs: &[ChainLink],
+    attestation: Option<&AttestationRequest>,
+) -> Result<VerifiedChain, ChainVerificationError> {
+    verify_chain_with(
+        |link, circuit_id| {
+            let descriptor = registry.descriptor(circuit_id).ok_or_else(|| {
+                ChainVerificationError::UnknownCircuit {
+                    link,
+                    circuit_id: circuit_id.to_string(),
+                }
+            })?;
+            let circuit = Circuit::new(&descriptor.meta)
+                .and_then(|circuit| descriptor.check(&circuit).map(|_| circuit))
+                .map_err(|reason| ChainVerificationError::InvalidProof { link, reason })?;
+            Ok((Arc::new(circuit), descriptor.role))
+        },
+        anchor_cmt,
+        proofs,
+        attestation,
+    )
+}
+
+/// [`verify_chain`] with the circuit of each link and its role loaded by `load`
+pub(crate) fn verify_chain_with(
+    mut load: impl FnMut(usize, &str) -> Result<(Arc<Circuit>, CircuitRole), ChainVerificationError>,
+    anchor_cmt: &str,
+    proofs: &[ChainLink],
+    attestation: Option<&AttestationRequest>,
+) -> Result<VerifiedChain, ChainVerificationError> {
+    if proofs.is_empty() {
+        return Err(ChainVerificationError::EmptyChain);
+    }
+    let anchor_cmt =
+        utils::hex_to_field(anchor_cmt).map_err(ChainVerificationError::InvalidAnchor)?;
+
+    let mut now = None;
+    let mut anchor_set = false;
+    let mut revocation_root = None;
+    let mut prev_cmt = anchor_cmt;
+    let mut cmts = Vec::with_capacity(proofs.len());
+    let mut disclosure = None;
+    let mut nym = None;
+    let mut nullifier = None;
+    let mut possession = None;
+    for (link, ChainLink { circuit_id, proof }) in proofs.iter().enumerate() {
+        let (circuit, role) = load(link, circuit_id)?;
+        let public_inputs = crate::verify::verify(&circuit, proof.clone())
+            .map_err(|reason| ChainVerificationError::InvalidProof { link, reason })?;
+        check_link_role(link, proofs.len(), circuit_id, role, public_inputs.path_len)?;
+
+        if public_inputs.prev_cmt != prev_cmt || (link > 0 && public_inputs.anchor_set) {
+            return Err(if link == 0 {
+                ChainVerificationError::AnchorMismatch
+            } else {
+                ChainVerificationError::CommitmentMismatch { link }
+            });
+        }
+        let time = (
+            public_inputs.now,
+            public_inputs.now_granularity.unwrap_or_default(),
+        );
+        if *now.get_or_insert(time) != time {
+            return Err(ChainVerificationError::TimeMismatch { link });
+        }
+        if *revocation_root.get_or_insert(public_inputs.revocation_root)
+            != public_inputs.revocation_root
+        {
+            return Err(ChainVerificationError::RevocationMismatch { link });
+        }
+
+        anchor_set |= public_inputs.anchor_set;
+        prev_cmt = public_inputs.next_cmt;
+        cmts.push(utils::field_to_hex(&public_inputs.next_cmt));
+        disclosure = public_inputs.attestation;
+        nym = public_inputs.nym;
+        nullifier = public_inputs.nullifier;
+        possession = public_inputs.possession;
+    }
+    check_request(disclosure.as_ref(), attestation).map_err(|reason| {
+        ChainVerificationError::RequestMismatch {
+            link: proofs.len() - 1,
+            reason,
+        }
+    })?;
+
+    let (now, now_granularity) = now.unwrap();
+    Ok(VerifiedChain {
+        now,
+        now_granularity,
+        anchor_set,
+        cmts,
+        revocation_root: revocation_root
+            .flatten()
+            .map(|root| utils::field_to_hex(&root)),
+        attestation: disclosure,
+        nym,
+        nullifier,
+        possession,
+    })
+}
+
+// The last link proves an end-entity certificate and every other link a CA certificate, whose
+// pathLenConstraint must allow the CA certificates of the links between it and the last one.
+fn check_link_role(
+    link: usize,
+    num_links: usize,
+    circuit_id: &str,
+    role: CircuitRole,
+    path_len: Option<u8>,
+) -> Result<(), ChainVerificationError> {
+    let expected = if link + 1 == num_links {
+        CircuitRole::Ee
+    } else {
+        CircuitRole::Ca
+    };
+    let mismatch = || ChainVerificationError::RoleMismatch {
+        link,
+        circuit_id: circuit_id.to_string(),
+        expected,
+    };
+    if role != expected {
+        return Err(mismatch());
+    }
+    if expected == CircuitRole::Ee {
+        return Ok(());
+    }
+    let Some(path_len) = path_len else {
+        return Err(mismatch());
+    };
+    if (path_len as usize) < num_links - link - 2 {
+        return Err(ChainVerificationError::PathLenExceeded { link, path_len });
+    }
+    Ok(())
+}
+
+pub struct VerifiedPublicInputs {
+    pub now: DateTime<Utc>,
+    pub now_granularity: TimeGranularity,
+    pub prev_cmt: String,
+    /// Whether `prev_cmt` is the root of an anchor set the issuer is a member of
+    pub anchor_set: bool,
+    pub next_cmt: String,
+    /// pathLenConstraint of a CA certificate, or [`NO_PATH_LEN`](crate::NO_PATH_LEN) if it has
+    /// none
+    pub path_len: Option<u8>,
+    /// Verifiers must check that this is the root of a current status list
+    pub revocation_root: Option<String>,
+    pub attestation: Option<AttestationDisclosure>,
+    /// Verifiers must check that `ctx` is their own context before relying on `nym`
+    pub nym: Option<Nym>,
+    /// Verifiers must check `scope` and `epoch`, and record `nullifier` in a
+    /// [`NullifierStore`](crate::nullifier::NullifierStore) to reject repeated actions
+    pub nullifier: Option<Nullifier>,
+    /// Challenge signed by the end-entity key, see [`verify_possession`]
+    pub possession: Option<Possession>,
+}
+
+pub fn verify(
+    circuit_meta: &CircuitMeta,
+    proof: Vec<u8>,
+    attestation: Option<AttestationRequest>,
+) -> Result<VerifiedPublicInputs, String> {
+    verify_with_circuit(&Circuit::new(circuit_meta)?, proof, attestation)
+}
+
+pub(crate) fn verify_with_circuit(
+    circuit: &Circuit,
+    proof: Vec<u8>,
+    attestation: Option<AttestationRequest>,
+) -> Result<VerifiedPublicInputs, String> {
+    let public_inputs = crate::verify::verify(circuit, proof)?;
+    check_request(public_inputs.attestation.as_ref(), attestation.as_ref())?;
+
+    Ok(VerifiedPublicInputs {
+        now: public_inputs.now,
+        now_granularity: public_inputs.now_granularity.unwrap_or_default(),
+        prev_cmt: utils::field_to_hex(&public_inputs.prev_cmt),
+        anchor_set: public_inputs.anchor_set,
+        next_cmt: utils::field_to_hex(&public_inputs.next_cmt),
+        path_len: public_inputs.path_len,
+        revocation_root: public_inputs
+            .revocation_root
+            .map(|root| utils::field_to_hex(&root)),
+        attestation: public_inputs.attestation,
+        nym: public_inputs.nym,
+        nullifier: public_inputs.nullifier,
+        possession: public_inputs.possession,
+    })
+}
+
+/// Like [`verify`], additionally checking that the proof shows possession of the end-entity key
+/// by a signature of `challenge`, which the verifier must have freshly issued for this session.
+pub fn verify_possession(
+    circuit_meta: &CircuitMeta,
+    proof: Vec<u8>,
+    attestation: Option<AttestationRequest>,
+    challenge: Vec<u8>,
+) -> Result<VerifiedPublicInputs, String> {
+    let verified = verify(circuit_meta, proof, attestation)?;
+    match &verified.possession {
+        Some(possession) if possession.challenge[..] == challenge[..] => Ok(verified),
+        Some(_) => Err("possession challenge does not match".to_string()),
+        None => Err("proof does not prove possession".to_string()),
+    }
+}
+
+/// Checks that the attestation properties disclosed by a proof answer the verifier's request.
+fn check_request(
+    disclosure: Option<&AttestationDisclosure>,
+    request: Option<&AttestationRequest>,
+) -> Result<(), String> {
+    match (disclosure, request) {
+        (Some(disclosure), Some(request)) => request.check(disclosure),
+        (Some(_), None) => Err(
+            "proof discloses attestation properties, but no attestation request is given"
+                .to_string(),
+        ),
+        (None, Some(_)) => Err("proof does not disclose attestation properties".to_string()),
+        (None, None) => Ok(()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        anchors::TrustAnchorStore, public_inputs::NO_PATH_LEN, revocation::RevocationList,
+    };
 
     #[test]
     fn test_commit_attrs() {
@@ -218,12 +1094,17 @@ mod tests {
         } = prove(
             &meta,
             cert,
-            now,
-            authority_key_id,
-            issuer_pk_x,
-            issuer_pk_y,
-            prev_cmt.to_string(),
-            prev_cmt_r.to_string(),
+            &IssuerInputs {
+                authority_key_id,
+                pk_x: issuer_pk_x,
+                pk_y: issuer_pk_y,
+                cmt: prev_cmt.to_string(),
+                cmt_r: prev_cmt_r.to_string(),
+            },
+            ProveOptions {
+                now,
+                ..Default::default()
+            },
         )
         .unwrap();
 
@@ -277,12 +1158,17 @@ mod tests {
         } = prove(
             &meta,
             cert,
-            now,
-            authority_key_id,
-            issuer_pk_x,
-            issuer_pk_y,
-            prev_cmt.to_string(),
-            prev_cmt_r.to_string(),
+            &IssuerInputs {
+                authority_key_id,
+                pk_x: issuer_pk_x,
+                pk_y: issuer_pk_y,
+                cmt: prev_cmt.to_string(),
+                cmt_r: prev_cmt_r.to_string(),
+            },
+            ProveOptions {
+                now,
+                ..Default::default()
+            },
         )
         .unwrap();
 
@@ -294,4 +1180,382 @@ mod tests {
         assert_eq!(next_cmt.len(), 64); // 32 bytes in hex
         assert_eq!(next_cmt_r.len(), 64); // 32 bytes in hex
     }
+
+    #[test]
+    fn test_verify_es256_ca() {
+        let meta = CircuitMeta::new(
+            "es256_ca".to_string(),
+            "data/es256_ca.json".to_string(),
+            "data/es256_ca.vk".to_string(),
+            "data/common.srs".to_string(),
+        )
+        .unwrap();
+        let cert = std::fs::read("test_data/es256_ca.der").unwrap();
+        let now = DateTime::parse_from_rfc3339("2025-09-01T12:34:56Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let authority_key_id = vec![
+            0xfe, 0x62, 0x6c, 0xdc, 0x2a, 0xe5, 0x80, 0xe7, 0x19, 0x6a, 0xca, 0x23, 0xdd, 0x23,
+            0xf1, 0x39, 0x02, 0x46, 0xa8, 0xa5,
+        ];
+        let issuer_pk_x = vec![
+            0x29, 0xc2, 0xef, 0x24, 0xa4, 0xbe, 0x89, 0xfd, 0x51, 0x35, 0x89, 0x24, 0xb3, 0x2e,
+            0x38, 0xd2, 0x5b, 0x64, 0x9e, 0x4e, 0x96, 0xff, 0x0b, 0x6f, 0x6b, 0xe2, 0x12, 0x87,
+            0x1b, 0xf5, 0x26, 0x27,
+        ];
+        let issuer_pk_y = vec![
+            0x9a, 0x9d, 0x6b, 0x56, 0x68, 0x29, 0xbf, 0x3a, 0xf8, 0xfe, 0xe0, 0x50, 0x94, 0x3f,
+            0xbb, 0x70, 0xab, 0xf5, 0xb1, 0xb3, 0x5a, 0xc1, 0xe3, 0xb8, 0x95, 0xee, 0x2e, 0xc0,
+            0xa8, 0x5a, 0xfb, 0xd2,
+        ];
+        let prev_cmt = "0ede28f511104f08069e07986707873be5cbba917f02f02407ad1fdd6838679b";
+        let prev_cmt_r = "deadbeef";
+        let ProofResult {
+            proof, next_cmt, ..
+        } = prove(
+            &meta,
+            cert,
+            &IssuerInputs {
+                authority_key_id,
+                pk_x: issuer_pk_x,
+                pk_y: issuer_pk_y,
+                cmt: prev_cmt.to_string(),
+                cmt_r: prev_cmt_r.to_string(),
+            },
+            ProveOptions {
+                now: Some(now),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let verified = verify(&meta, proof.clone(), None).unwrap();
+        assert_eq!(verified.now, now);
+        assert_eq!(verified.prev_cmt, prev_cmt);
+        assert_eq!(verified.next_cmt, next_cmt);
+
+        let mut tampered = proof;
+        let last = tampered.len() - 1;
+        tampered[last] ^= 0x01;
+        assert!(verify(&meta, tampered, None).is_err());
+    }
+
+    fn test_registry() -> CircuitRegistry {
+        CircuitRegistry::from_dir("data").unwrap()
+    }
+
+    #[test]
+    fn test_prove_chain() {
+        let chain = vec![
+            std::fs::read("test_data/es256_ee.der").unwrap(),
+            std::fs::read("test_data/es256_ca.der").unwrap(),
+        ];
+        let now = DateTime::parse_from_rfc3339("2025-09-01T12:34:56Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        let ChainProofResult {
+            anchor_cmt,
+            anchor_cmt_r,
+            proofs,
+        } = prove_chain(
+            &test_registry(),
+            chain,
+            None,
+            ProveOptions {
+                now: Some(now),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(anchor_cmt.len(), 64); // 32 bytes in hex
+        assert_eq!(anchor_cmt_r.len(), 64); // 32 bytes in hex
+        assert_eq!(proofs.len(), 1);
+        assert_eq!(proofs[0].circuit_id, "es256_ee");
+
+        let (public_inputs, _) = crate::split_proof(&proofs[0].proof).unwrap();
+        assert_eq!(public_inputs.now, now);
+        assert_eq!(utils::field_to_hex(&public_inputs.prev_cmt), anchor_cmt);
+        assert_eq!(
+            utils::field_to_hex(&public_inputs.next_cmt),
+            proofs[0].next_cmt
+        );
+    }
+
+    #[test]
+    fn test_prove_chain_rejects_misordered_chain() {
+        let chain = vec![
+            std::fs::read("test_data/es256_ca.der").unwrap(),
+            std::fs::read("test_data/es256_ee.der").unwrap(),
+        ];
+        assert!(prove_chain(&test_registry(), chain, None, ProveOptions::default()).is_err());
+        assert!(prove_chain(&test_registry(), vec![], None, ProveOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_prove_chain_rejects_path_len_violation() {
+        // es256_ca_path_len has a pathLenConstraint of 0 but issued the CA es256_ca_sub
+        let chain = vec![
+            std::fs::read("test_data/es256_ca_sub.der").unwrap(),
+            std::fs::read("test_data/es256_ca_path_len.der").unwrap(),
+            std::fs::read("test_data/test_root.der").unwrap(),
+        ];
+        let err = prove_chain(&test_registry(), chain, None, ProveOptions::default())
+            .err()
+            .unwrap();
+        assert!(err.contains("pathLenConstraint"), "{}", err);
+    }
+
+    #[test]
+    fn test_prove_chain_rejects_anchor_set_with_revocation() {
+        let chain = vec![
+            std::fs::read("test_data/es256_ee.der").unwrap(),
+            std::fs::read("test_data/es256_ca.der").unwrap(),
+        ];
+        let mut store = TrustAnchorStore::new();
+        store.load_file("test_data/es256_ca.der").unwrap();
+        let anchor_set = store.anchor_set(4).unwrap();
+        let tree = RevocationTree::new(&RevocationList::new(), 4).unwrap();
+        let options = ProveOptions {
+            revocation: Some(&tree),
+            ..Default::default()
+        };
+        let err = prove_chain_with(
+            |_, _| panic!("no circuit should be selected"),
+            chain,
+            Some(&anchor_set),
+            options,
+        )
+        .err()
+        .unwrap();
+        assert!(err.contains("anchor set cannot be combined"), "{}", err);
+    }
+
+    #[test]
+    fn test_prove_chain_selects_attested_variant() {
+        let chain = vec![
+            std::fs::read("test_data/es256_ee.der").unwrap(),
+            std::fs::read("test_data/es256_ca.der").unwrap(),
+        ];
+        let options = || ProveOptions {
+            attestation: Some(AttestationRequest {
+                nonce: vec![0x00],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let mut variants = Vec::new();
+        let err = prove_chain_with(
+            |_, variant| {
+                variants.push(variant);
+                Err("no circuit".to_string())
+            },
+            chain.clone(),
+            None,
+            options(),
+        )
+        .err()
+        .unwrap();
+        assert_eq!(err, "chain[0]: no circuit");
+        assert_eq!(
+            variants,
+            vec![CircuitVariant {
+                attestation: true,
+                ..Default::default()
+            }]
+        );
+
+        // data/ has no attested end-entity circuit, rather than proving without the attestation
+        let err = prove_chain(&test_registry(), chain, None, options())
+            .err()
+            .unwrap();
+        assert!(err.contains("disclosing attestation properties"), "{}", err);
+    }
+
+    #[test]
+    fn test_verify_chain() {
+        let chain = vec![
+            std::fs::read("test_data/es256_ee.der").unwrap(),
+            std::fs::read("test_data/es256_ca.der").unwrap(),
+        ];
+        let now = DateTime::parse_from_rfc3339("2025-09-01T12:34:56Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let registry = test_registry();
+        let ChainProofResult {
+            anchor_cmt, proofs, ..
+        } = prove_chain(
+            &registry,
+            chain,
+            None,
+            ProveOptions {
+                now: Some(now),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let links: Vec<ChainLink> = proofs.iter().map(ChainLink::from).collect();
+
+        let verified = verify_chain(&registry, &anchor_cmt, &links, None).unwrap();
+        assert_eq!(verified.now, now);
+        assert_eq!(verified.cmts, vec![proofs[0].next_cmt.clone()]);
+
+        let other_anchor = "0ede28f511104f08069e07986707873be5cbba917f02f02407ad1fdd6838679b";
+        assert_eq!(
+            verify_chain(&registry, other_anchor, &links, None).err(),
+            Some(ChainVerificationError::AnchorMismatch)
+        );
+
+        let unknown = vec![ChainLink {
+            circuit_id: "rsa_sha256_ca".to_string(),
+            proof: links[0].proof.clone(),
+        }];
+        assert_eq!(
+            verify_chain(&registry, &anchor_cmt, &unknown, None)
+                .err()
+                .and_then(|e| e.link()),
+            Some(0)
+        );
+
+        assert_eq!(
+            verify_chain(&registry, &anchor_cmt, &[], None).err(),
+            Some(ChainVerificationError::EmptyChain)
+        );
+
+        // an end-entity proof cannot stand for a CA link
+        let doubled: Vec<ChainLink> = proofs.iter().chain(&proofs).map(ChainLink::from).collect();
+        assert_eq!(
+            verify_chain(&registry, &anchor_cmt, &doubled, None).err(),
+            Some(ChainVerificationError::RoleMismatch {
+                link: 0,
+                circuit_id: links[0].circuit_id.clone(),
+                expected: CircuitRole::Ca,
+            })
+        );
+
+        // es256_ee does not disclose attestation properties
+        let request = AttestationRequest {
+            nonce: vec![0x00],
+            ..Default::default()
+        };
+        assert_eq!(
+            verify_chain(&registry, &anchor_cmt, &links, Some(&request))
+                .err()
+                .and_then(|e| e.link()),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn test_check_link_role() {
+        let check = |link, role, path_len| check_link_role(link, 4, "es256_ca", role, path_len);
+        assert!(check(0, CircuitRole::Ca, Some(NO_PATH_LEN)).is_ok());
+        assert!(check(0, CircuitRole::Ca, Some(2)).is_ok());
+        assert!(check(1, CircuitRole::Ca, Some(1)).is_ok());
+        assert!(check(2, CircuitRole::Ca, Some(0)).is_ok());
+        assert!(check(3, CircuitRole::Ee, None).is_ok());
+
+        assert_eq!(
+            check(0, CircuitRole::Ca, Some(1)),
+            Err(ChainVerificationError::PathLenExceeded {
+                link: 0,
+                path_len: 1
+            })
+        );
+        assert!(matches!(
+            check(2, CircuitRole::Ee, None),
+            Err(ChainVerificationError::RoleMismatch {
+                link: 2,
+                expected: CircuitRole::Ca,
+                ..
+            })
+        ));
+        // a chain cannot end with a CA certificate
+        assert!(matches!(
+            check(3, CircuitRole::Ca, Some(NO_PATH_LEN)),
+            Err(ChainVerificationError::RoleMismatch {
+                link: 3,
+                expected: CircuitRole::Ee,
+                ..
+            })
+        ));
+        // a CA circuit of a stale manifest that discloses no pathLenConstraint
+        assert!(check(1, CircuitRole::Ca, None).is_err());
+    }
+
+    #[test]
+    fn test_check_request() {
+        let app_id = "082fa0ba5ca89c1918e3cedccde7bb5968fab935d664c3e8ba68b267b995de74";
+        let predicate = Predicate::new(crate::PredicateAttribute::OsPatchLevel, 202405);
+        let attestation = AttestationDisclosure {
+            security_level: crate::SecurityLevel::StrongBox,
+            device_locked: true,
+            verified_boot_state: crate::VerifiedBootState::Verified,
+            challenge: vec![0x0a, 0x0b],
+            predicates: vec![crate::PredicateResult {
+                predicate,
+                holds: true,
+            }],
+            app_id_hash: None,
+            app_id_allow_list: vec![utils::hex_to_field(app_id).unwrap()],
+        };
+        let request = AttestationRequest {
+            nonce: vec![0x0a, 0x0b],
+            predicates: vec![predicate],
+            disclose_app_id: false,
+            app_id_allow_list: vec![app_id.to_string()],
+        };
+        assert!(check_request(Some(&attestation), Some(&request)).is_ok());
+        assert!(check_request(Some(&attestation), None).is_err());
+        assert!(check_request(None, Some(&request)).is_err());
+        assert!(check_request(None, None).is_ok());
+
+        let mismatches = [
+            AttestationRequest {
+                nonce: vec![0x0a],
+                ..request.clone()
+            },
+            AttestationRequest {
+                predicates: Vec::new(),
+                ..request.clone()
+            },
+            AttestationRequest {
+                disclose_app_id: true,
+                ..request.clone()
+            },
+            AttestationRequest {
+                app_id_allow_list: Vec::new(),
+                ..request.clone()
+            },
+        ];
+        for request in mismatches {
+            assert!(check_request(Some(&attestation), Some(&request)).is_err());
+        }
+    }
+
+    #[test]
+    fn test_commit_cert() {
+        let cert = std::fs::read("test_data/es256_ca.der").unwrap();
+        let CommitResult { cmt, r } =
+            commit_cert(cert.clone(), Some("feedface".to_string())).unwrap();
+        assert_eq!(
+            cmt,
+            "2a296b0c9a2c8b4c6c56357c632860849f42a4defa6b491b2421a962a3543f5c"
+        );
+        assert_eq!(r.len(), 64); // 32 bytes in hex
+
+        let CommitResult {
+            cmt: anchor_cmt, ..
+        } = commit_anchor(cert, Some("feedface".to_string())).unwrap();
+        assert_eq!(anchor_cmt, cmt);
+    }
+
+    #[test]
+    fn test_commit_anchor_rejects_end_entity() {
+        let cert = std::fs::read("test_data/es256_ee.der").unwrap();
+        assert!(commit_cert(cert.clone(), None).is_ok());
+        assert!(commit_anchor(cert, None).is_err());
+    }
 }
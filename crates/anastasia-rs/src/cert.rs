@@ -4,7 +4,8 @@ use x509_parser::prelude::*;
 
 use crate::utils::to_fixed_array;
 
-fn extract_ecdsa_der(signature_value: &[u8]) -> Result<Vec<u8>, String> {
+/// Converts a DER ECDSA-Sig-Value on secp256r1 into `r || s`, with `s` normalized to low-s form.
+pub(crate) fn extract_ecdsa_der(signature_value: &[u8]) -> Result<Vec<u8>, String> {
     let (_, seq) =
         parse_der_sequence(signature_value).map_err(|e| format!("parse error: {e:?}"))?;
     let items = match seq.content {
@@ -14,17 +15,9 @@ fn extract_ecdsa_der(signature_value: &[u8]) -> Result<Vec<u8>, String> {
     if items.len() != 2 {
         return Err("sequence does not have 2 elements".to_string());
     }
-    let r = match items[0].content {
-        DerObjectContent::Integer(ref data) => {
-            let d = data.to_vec();
-            if d.len() == 33 && d[0] == 0 {
-                d[1..].to_vec()
-            } else {
-                d
-            }
-        }
-        _ => return Err("first element is not integer".to_string()),
-    };
+    let r = items[0]
+        .as_biguint()
+        .map_err(|_| "first element is not integer".to_string())?;
 
     // normalize s to low-s form
     let s_uint = items[1]
@@ -35,22 +28,59 @@ fn extract_ecdsa_der(signature_value: &[u8]) -> Result<Vec<u8>, String> {
         16,
     )
     .ok_or("Failed to parse secp256r1 order")?; // TODO: optimize $n$ for secp256r1
+    if r >= n || s_uint >= n {
+        return Err("signature value out of range".to_string());
+    }
     let n_half = &n >> 1;
     let s_norm = if s_uint > n_half {
         &n - &s_uint
     } else {
         s_uint
     };
-    let s = s_norm.to_bytes_be();
 
-    let mut res = Vec::with_capacity(64);
-    res.extend_from_slice(&r);
-    res.extend_from_slice(&s);
+    // r and s are minimally encoded, so pad them to 32 bytes each
+    let mut res = vec![0u8; 64];
+    let r = r.to_bytes_be();
+    let s = s_norm.to_bytes_be();
+    res[32 - r.len()..32].copy_from_slice(&r);
+    res[64 - s.len()..].copy_from_slice(&s);
     Ok(res)
 }
 
+/// notAfter of a certificate with no well-defined expiration date, i.e. the GeneralizedTime
+/// 99991231235959Z (RFC 5280, 4.1.2.5)
+pub const NO_WELL_DEFINED_EXPIRATION: [u8; 7] = [0x27, 0x0f, 12, 31, 23, 59, 59];
+
+/// Bit of keyCertSign in [`ParsedCert::key_usage`]
+const KEY_CERT_SIGN: u16 = 1 << 5;
+
+/// Signature algorithm of a certificate, named as in JWA (RFC 7518) in circuit manifests
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureAlgorithm {
+    /// ECDSA on secp256r1 with SHA-256
+    Es256,
+}
+
+impl SignatureAlgorithm {
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        match name {
+            "ES256" => Ok(SignatureAlgorithm::Es256),
+            _ => Err(format!("Unsupported signature algorithm `{}`", name)),
+        }
+    }
+
+    fn from_oid(oid: &x509_parser::der_parser::oid::Oid) -> Result<Self, String> {
+        if *oid == x509_parser::oid_registry::OID_SIG_ECDSA_WITH_SHA256 {
+            Ok(SignatureAlgorithm::Es256)
+        } else {
+            Err(format!("Unsupported signature algorithm {}", oid))
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct ParsedCert {
+    pub signature_algorithm: SignatureAlgorithm,
     pub signature: [u8; 64],
     pub serial_number: [u8; 20],
     pub serial_number_len: u32,
@@ -58,6 +88,10 @@ pub struct ParsedCert {
     pub issuer_len: u32,
     pub not_before: [u8; 7],
     pub not_after: [u8; 7],
+    /// Whether `not_before` is encoded as GeneralizedTime rather than UTCTime
+    pub not_before_generalized: bool,
+    /// Whether `not_after` is encoded as GeneralizedTime rather than UTCTime
+    pub not_after_generalized: bool,
     pub subject: [u8; 124],
     pub subject_len: u32,
     pub subject_pk_x: [u8; 32],
@@ -66,11 +100,21 @@ pub struct ParsedCert {
     pub authority_key_identifier: [u8; 20],
     pub subject_key_identifier_index: u32,
     pub authority_key_identifier_index: u32,
-    pub basic_constraints_ca_index: u32,
-    pub key_usage_key_cert_sign_index: u32,
-    pub key_usage_digital_signature_index: u32,
+    pub basic_constraints_index: u32,
+    pub basic_constraints_critical: bool,
+    pub basic_constraints_ca: bool,
+    pub basic_constraints_path_len: Option<u32>,
+    pub key_usage_index: u32,
+    pub key_usage_critical: bool,
+    /// KeyUsage bits; bit `i` is the `i`-th named bit of RFC 5280, 4.2.1.3 (digitalSignature = 0)
+    pub key_usage: u16,
+    /// Concatenation of the extensions not modelled by the circuit, in certificate order
     pub extra_extension: Vec<u8>,
     pub extra_extension_len: u32,
+    /// Start offset of each extension within `extra_extension`
+    pub extra_extension_offsets: Vec<u32>,
+    /// Length of the DER-encoded TBSCertificate
+    pub tbs_len: u32,
 }
 
 impl ParsedCert {
@@ -78,6 +122,9 @@ impl ParsedCert {
         let (_, parsed_cert) = X509Certificate::from_der(cert)
             .map_err(|e| format!("Failed to parse certificate: {}", e))?;
 
+        let signature_algorithm =
+            SignatureAlgorithm::from_oid(&parsed_cert.signature_algorithm.algorithm)?;
+
         // parse signature value
         let signature_value = parsed_cert.signature_value.as_ref();
         let signature = extract_ecdsa_der(signature_value)
@@ -97,44 +144,57 @@ impl ParsedCert {
 
         // parse validity
         let validity = &parsed_cert.tbs_certificate.validity;
-        let not_before = parse_asn1time(&validity.not_before);
-        let not_after = parse_asn1time(&validity.not_after);
+        let not_before = parse_asn1time(&validity.not_before)?;
+        let not_after = parse_asn1time(&validity.not_after)?;
 
         // parse subject public key info
-        let spki = &parsed_cert
-            .tbs_certificate
-            .subject_pki
-            .subject_public_key
-            .data;
-        if spki[0] != 0x04 {
-            return Err("Only uncompressed EC public key is supported".to_string());
-        }
-        let spk_x = &spki[1..33];
-        let spk_y = &spki[33..65];
+        let (subject_pk_x, subject_pk_y) =
+            parse_p256_public_key(&parsed_cert.tbs_certificate.subject_pki)?;
 
         // parse extensions
         let mut subject_key_identifier: Vec<u8> = Vec::with_capacity(20);
         let mut subject_key_identifier_index = 0;
         let mut authority_key_identifier: Vec<u8> = Vec::with_capacity(20);
         let mut authority_key_identifier_index = 0;
-        let mut basic_constraints_ca_index = 0;
-        let mut key_usage_key_cert_sign_index = 0;
-        let mut key_usage_digital_signature_index = 0;
+        let mut basic_constraints_index = 0;
+        let mut basic_constraints_critical = false;
+        let mut basic_constraints_ca = false;
+        let mut basic_constraints_path_len = None;
+        let mut key_usage_index = 0;
+        let mut key_usage_critical = false;
+        let mut key_usage = 0;
         let mut extra_extension: Vec<u8> = Vec::new();
-        let mut extra_extension_len = 0;
+        let mut extra_extension_offsets: Vec<u32> = Vec::new();
         for (i, ext) in parsed_cert.extensions().iter().enumerate() {
+            // Unsupported extensions and those x509-parser knows but the circuit doesn't
+            // model are re-emitted from their raw DER
+            if !is_standard_extension(ext) {
+                extra_extension_offsets.push(extra_extension.len() as u32);
+                extra_extension.extend(serialize_extension(
+                    ext.oid.as_bytes(),
+                    ext.critical,
+                    ext.value,
+                )?);
+                continue;
+            }
+            // The circuit appends the extra extensions after the standard ones
+            if !extra_extension_offsets.is_empty() {
+                return Err(format!(
+                    "Extension {} must precede the extra extensions",
+                    ext.oid
+                ));
+            }
             match ext.parsed_extension() {
                 ParsedExtension::KeyUsage(ku) => {
-                    if ku.key_cert_sign() {
-                        key_usage_key_cert_sign_index = i + 1;
-                    } else if ku.digital_signature() {
-                        key_usage_digital_signature_index = i + 1;
-                    }
+                    key_usage_index = i + 1;
+                    key_usage_critical = ext.critical;
+                    key_usage = ku.flags;
                 }
                 ParsedExtension::BasicConstraints(bc) => {
-                    if bc.ca {
-                        basic_constraints_ca_index = i + 1;
-                    }
+                    basic_constraints_index = i + 1;
+                    basic_constraints_critical = ext.critical;
+                    basic_constraints_ca = bc.ca;
+                    basic_constraints_path_len = bc.path_len_constraint;
                 }
                 ParsedExtension::AuthorityKeyIdentifier(aki) => {
                     authority_key_identifier = match &aki.key_identifier {
@@ -149,48 +209,12 @@ impl ParsedCert {
                     subject_key_identifier = ski.0.to_vec();
                     subject_key_identifier_index = i + 1;
                 }
-                ParsedExtension::UnsupportedExtension { oid } => {
-                    let oid_bytes = oid.as_bytes();
-                    let oid_len = oid_bytes.len();
-                    let oid_len_bytes = serialize_length(oid_len)?;
-
-                    let critical_bytes = match ext.critical {
-                        true => vec![0x01, 0x01, 0xff],
-                        false => vec![],
-                    };
-                    let critical_len = critical_bytes.len();
-
-                    let value_bytes = ext.value.to_vec();
-                    let value_len = value_bytes.len();
-                    let value_len_bytes = serialize_length(value_len)?;
-
-                    let total_len = 1 // tag for OID
-                    + oid_len_bytes.len() // length of OID length bytes
-                    + oid_len // length of OID
-                    + critical_len // length of critical
-                    + 1 // tag for OCTET STRING
-                    + value_len_bytes.len() // length of value length bytes
-                    + value_len; // length of value
-                    let total_len_bytes = serialize_length(total_len)?;
-
-                    let mut ext_bytes = Vec::with_capacity(total_len + total_len_bytes.len() + 1);
-                    ext_bytes.push(0x30); // SEQUENCE
-                    ext_bytes.extend_from_slice(&total_len_bytes);
-                    ext_bytes.push(0x06); // OID
-                    ext_bytes.extend_from_slice(&oid_len_bytes);
-                    ext_bytes.extend_from_slice(oid_bytes);
-                    ext_bytes.extend_from_slice(&critical_bytes);
-                    ext_bytes.push(0x04); // OCTET STRING
-                    ext_bytes.extend_from_slice(&value_len_bytes);
-                    ext_bytes.extend_from_slice(&value_bytes);
-                    extra_extension_len = ext_bytes.len();
-                    extra_extension = ext_bytes;
-                }
                 _ => {}
             }
         }
 
         Ok(ParsedCert {
+            signature_algorithm,
             signature: signature
                 .try_into()
                 .map_err(|_| "Signature length is not 64 bytes".to_string())?,
@@ -207,24 +231,12 @@ impl ParsedCert {
             issuer_len: issuer_len as u32,
             not_before,
             not_after,
+            not_before_generalized: validity.not_before.is_generalizedtime(),
+            not_after_generalized: validity.not_after.is_generalizedtime(),
             subject: { to_fixed_array::<124>(subject)? },
             subject_len: subject_len as u32,
-            subject_pk_x: {
-                let mut buf = [0u8; 32];
-                if spk_x.len() != 32 {
-                    return Err("Public key X length is not 32 bytes".to_string());
-                }
-                buf.copy_from_slice(spk_x);
-                buf
-            },
-            subject_pk_y: {
-                let mut buf = [0u8; 32];
-                if spk_y.len() != 32 {
-                    return Err("Public key Y length is not 32 bytes".to_string());
-                }
-                buf.copy_from_slice(spk_y);
-                buf
-            },
+            subject_pk_x,
+            subject_pk_y,
             subject_key_identifier: {
                 let mut buf = [0u8; 20];
                 if subject_key_identifier.len() != 0 && subject_key_identifier.len() != 20 {
@@ -247,15 +259,193 @@ impl ParsedCert {
             },
             subject_key_identifier_index: subject_key_identifier_index as u32,
             authority_key_identifier_index: authority_key_identifier_index as u32,
-            basic_constraints_ca_index: basic_constraints_ca_index as u32,
-            key_usage_key_cert_sign_index: key_usage_key_cert_sign_index as u32,
-            key_usage_digital_signature_index: key_usage_digital_signature_index as u32,
+            basic_constraints_index: basic_constraints_index as u32,
+            basic_constraints_critical,
+            basic_constraints_ca,
+            basic_constraints_path_len,
+            key_usage_index: key_usage_index as u32,
+            key_usage_critical,
+            key_usage,
+            extra_extension_len: extra_extension.len() as u32,
             extra_extension,
-            extra_extension_len: extra_extension_len as u32,
+            extra_extension_offsets,
+            tbs_len: parsed_cert.tbs_certificate.as_ref().len() as u32,
+        })
+    }
+}
+
+impl ParsedCert {
+    /// Returns false for certificates with no well-defined expiration date.
+    pub fn has_expiration(&self) -> bool {
+        !(self.not_after_generalized && self.not_after == NO_WELL_DEFINED_EXPIRATION)
+    }
+
+    /// Whether the certificate is a CA that may sign certificates, as the CA circuits assert:
+    /// BasicConstraints with cA set and KeyUsage with keyCertSign set.
+    pub fn may_sign_certificates(&self) -> bool {
+        self.basic_constraints_index != 0
+            && self.basic_constraints_ca
+            && self.key_usage_index != 0
+            && self.key_usage & KEY_CERT_SIGN != 0
+    }
+
+    pub fn subject_attrs(&self) -> SubjectAttrs {
+        SubjectAttrs {
+            subject: self.subject,
+            subject_len: self.subject_len,
+            subject_key_identifier: self.subject_key_identifier,
+            subject_pk_x: self.subject_pk_x,
+            subject_pk_y: self.subject_pk_y,
+            is_ca: self.basic_constraints_ca,
+            path_len: self.basic_constraints_path_len,
+        }
+    }
+}
+
+/// Subject attributes committed to by `next_cmt`, i.e. the attributes an issued
+/// certificate's `prev_cmt` opens to.
+#[derive(Debug, Clone, Copy)]
+pub struct SubjectAttrs {
+    pub subject: [u8; 124],
+    pub subject_len: u32,
+    pub subject_key_identifier: [u8; 20],
+    pub subject_pk_x: [u8; 32],
+    pub subject_pk_y: [u8; 32],
+    pub is_ca: bool,
+    /// pathLenConstraint of a CA certificate
+    pub path_len: Option<u32>,
+}
+
+impl SubjectAttrs {
+    /// Unlike [`ParsedCert::from_der`], this does not look at the signature of the certificate,
+    /// so it also accepts issuers (e.g. trust anchors) signed with other algorithms.
+    pub fn from_der(cert: &[u8]) -> Result<Self, String> {
+        let (_, parsed_cert) = X509Certificate::from_der(cert)
+            .map_err(|e| format!("Failed to parse certificate: {}", e))?;
+
+        let subject = parsed_cert.tbs_certificate.subject.as_raw();
+        let (subject_pk_x, subject_pk_y) =
+            parse_p256_public_key(&parsed_cert.tbs_certificate.subject_pki)?;
+
+        let mut subject_key_identifier = [0u8; 20];
+        let mut is_ca = false;
+        let mut path_len = None;
+        for ext in parsed_cert.extensions() {
+            match ext.parsed_extension() {
+                ParsedExtension::SubjectKeyIdentifier(ski) => {
+                    subject_key_identifier = ski
+                        .0
+                        .try_into()
+                        .map_err(|_| "SubjectKeyIdentifier length must be 20 bytes".to_string())?;
+                }
+                ParsedExtension::BasicConstraints(bc) => {
+                    is_ca = bc.ca;
+                    path_len = bc.path_len_constraint;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(SubjectAttrs {
+            subject: to_fixed_array::<124>(subject)?,
+            subject_len: subject.len() as u32,
+            subject_key_identifier,
+            subject_pk_x,
+            subject_pk_y,
+            is_ca,
+            path_len,
         })
     }
 }
 
+fn parse_p256_public_key(spki: &SubjectPublicKeyInfo) -> Result<([u8; 32], [u8; 32]), String> {
+    let key = &spki.subject_public_key.data;
+    if key.len() != 65 || key[0] != 0x04 {
+        return Err("Only uncompressed P-256 public key is supported".to_string());
+    }
+    let mut x = [0u8; 32];
+    let mut y = [0u8; 32];
+    x.copy_from_slice(&key[1..33]);
+    y.copy_from_slice(&key[33..65]);
+    Ok((x, y))
+}
+
+fn is_standard_extension(ext: &X509Extension) -> bool {
+    match ext.parsed_extension() {
+        ParsedExtension::SubjectKeyIdentifier(_) | ParsedExtension::AuthorityKeyIdentifier(_) => {
+            true
+        }
+        // The circuit supports a pathLenConstraint of at most 127
+        ParsedExtension::BasicConstraints(bc) => {
+            bc.path_len_constraint.is_none_or(|len| len < 128)
+                && serialize_basic_constraints(bc) == ext.value
+        }
+        // The circuit re-encodes the bits in DER, so other encodings can't be modelled
+        ParsedExtension::KeyUsage(ku) => serialize_key_usage(ku.flags) == ext.value,
+        _ => false,
+    }
+}
+
+/// DER encoding of a BasicConstraints SEQUENCE with a pathLenConstraint below 128.
+fn serialize_basic_constraints(bc: &BasicConstraints) -> Vec<u8> {
+    let mut content = Vec::with_capacity(6);
+    if bc.ca {
+        content.extend_from_slice(&[0x01, 0x01, 0xff]);
+    }
+    if let Some(path_len) = bc.path_len_constraint {
+        content.extend_from_slice(&[0x02, 0x01, path_len as u8]);
+    }
+
+    let mut seq = vec![0x30, content.len() as u8];
+    seq.extend(content);
+    seq
+}
+
+/// DER encoding of a KeyUsage BIT STRING, with trailing zero bits removed.
+fn serialize_key_usage(flags: u16) -> Vec<u8> {
+    let bits = 16 - flags.leading_zeros() as usize;
+    let content_len = bits.div_ceil(8);
+    let unused_bits = content_len * 8 - bits;
+
+    let mut bit_string = vec![0x03, 1 + content_len as u8, unused_bits as u8];
+    let reversed = flags.reverse_bits().to_be_bytes();
+    bit_string.extend_from_slice(&reversed[..content_len]);
+    bit_string
+}
+
+/// DER encoding of an `Extension`: SEQUENCE { extnID, critical (only if TRUE), extnValue }.
+fn serialize_extension(oid: &[u8], critical: bool, value: &[u8]) -> Result<Vec<u8>, String> {
+    let oid_len_bytes = serialize_length(oid.len())?;
+
+    let critical_bytes = match critical {
+        true => vec![0x01, 0x01, 0xff],
+        false => vec![],
+    };
+
+    let value_len_bytes = serialize_length(value.len())?;
+
+    let total_len = 1 // tag for OID
+        + oid_len_bytes.len() // length of OID length bytes
+        + oid.len() // length of OID
+        + critical_bytes.len() // length of critical
+        + 1 // tag for OCTET STRING
+        + value_len_bytes.len() // length of value length bytes
+        + value.len(); // length of value
+    let total_len_bytes = serialize_length(total_len)?;
+
+    let mut ext_bytes = Vec::with_capacity(total_len + total_len_bytes.len() + 1);
+    ext_bytes.push(0x30); // SEQUENCE
+    ext_bytes.extend_from_slice(&total_len_bytes);
+    ext_bytes.push(0x06); // OID
+    ext_bytes.extend_from_slice(&oid_len_bytes);
+    ext_bytes.extend_from_slice(oid);
+    ext_bytes.extend_from_slice(&critical_bytes);
+    ext_bytes.push(0x04); // OCTET STRING
+    ext_bytes.extend_from_slice(&value_len_bytes);
+    ext_bytes.extend_from_slice(value);
+    Ok(ext_bytes)
+}
+
 pub fn serialize_length(len: usize) -> Result<Vec<u8>, String> {
     let mut len_bytes = Vec::new();
     if len < 128 {
@@ -274,8 +464,12 @@ pub fn serialize_length(len: usize) -> Result<Vec<u8>, String> {
     Ok(len_bytes)
 }
 
-fn parse_asn1time(dt: &ASN1Time) -> [u8; 7] {
+fn parse_asn1time(dt: &ASN1Time) -> Result<[u8; 7], String> {
     let datetime = dt.to_datetime();
+    // RFC 5280, 4.1.2.5.2: GeneralizedTime values MUST NOT include fractional seconds
+    if datetime.nanosecond() != 0 {
+        return Err("Validity with fractional seconds is not supported".to_string());
+    }
     let year = datetime.year() as u16;
     let year = year.to_be_bytes();
     let month = datetime.month() as u8;
@@ -283,7 +477,7 @@ fn parse_asn1time(dt: &ASN1Time) -> [u8; 7] {
     let hour = datetime.hour() as u8;
     let minute = datetime.minute() as u8;
     let second = datetime.second() as u8;
-    [year[0], year[1], month, day, hour, minute, second]
+    Ok([year[0], year[1], month, day, hour, minute, second])
 }
 
 #[cfg(test)]
@@ -303,8 +497,13 @@ mod tests {
             ]
         );
         assert_eq!(parsed_cert.serial_number_len, 17);
+        assert_eq!(parsed_cert.signature_algorithm, SignatureAlgorithm::Es256);
+        assert_eq!(parsed_cert.tbs_len, 400);
         assert_eq!(parsed_cert.not_before, [0x07, 0xe9, 8, 21, 17, 27, 4]);
         assert_eq!(parsed_cert.not_after, [0x07, 0xe9, 9, 16, 15, 20, 10]);
+        assert!(!parsed_cert.not_before_generalized);
+        assert!(!parsed_cert.not_after_generalized);
+        assert!(parsed_cert.has_expiration());
         assert_eq!(
             parsed_cert.issuer,
             [
@@ -367,9 +566,14 @@ mod tests {
         );
         assert_eq!(parsed_cert.subject_key_identifier_index, 1);
         assert_eq!(parsed_cert.authority_key_identifier_index, 2);
-        assert_eq!(parsed_cert.basic_constraints_ca_index, 3);
-        assert_eq!(parsed_cert.key_usage_key_cert_sign_index, 4);
-        assert_eq!(parsed_cert.key_usage_digital_signature_index, 0);
+        assert_eq!(parsed_cert.basic_constraints_index, 3);
+        assert!(parsed_cert.basic_constraints_critical);
+        assert!(parsed_cert.basic_constraints_ca);
+        assert_eq!(parsed_cert.basic_constraints_path_len, None);
+        assert_eq!(parsed_cert.key_usage_index, 4);
+        assert!(parsed_cert.key_usage_critical);
+        assert_eq!(parsed_cert.key_usage, 0x20); // keyCertSign
+        assert!(parsed_cert.may_sign_certificates());
         assert_eq!(
             parsed_cert.extra_extension,
             [
@@ -380,6 +584,7 @@ mod tests {
             ]
         );
         assert_eq!(parsed_cert.extra_extension_len, 28);
+        assert_eq!(parsed_cert.extra_extension_offsets, [0]);
     }
 
     #[test]
@@ -459,9 +664,12 @@ mod tests {
         );
         assert_eq!(parsed_cert.subject_key_identifier_index, 0);
         assert_eq!(parsed_cert.authority_key_identifier_index, 0);
-        assert_eq!(parsed_cert.basic_constraints_ca_index, 0);
-        assert_eq!(parsed_cert.key_usage_key_cert_sign_index, 0);
-        assert_eq!(parsed_cert.key_usage_digital_signature_index, 1);
+        assert_eq!(parsed_cert.basic_constraints_index, 0);
+        assert!(!parsed_cert.basic_constraints_ca);
+        assert!(!parsed_cert.may_sign_certificates());
+        assert_eq!(parsed_cert.key_usage_index, 1);
+        assert!(parsed_cert.key_usage_critical);
+        assert_eq!(parsed_cert.key_usage, 0x01); // digitalSignature
         assert_eq!(
             parsed_cert.extra_extension,
             [
@@ -491,5 +699,159 @@ mod tests {
             ]
         );
         assert_eq!(parsed_cert.extra_extension_len, 298);
+        assert_eq!(parsed_cert.extra_extension_offsets, [0]);
+    }
+
+    #[test]
+    fn test_parse_multiple_extra_extensions() {
+        let cert = include_bytes!("../test_data/es256_ca_multi_ext.der");
+        let parsed_cert = ParsedCert::from_der(cert).unwrap();
+
+        assert_eq!(parsed_cert.key_usage_index, 4);
+        assert_eq!(
+            parsed_cert.extra_extension,
+            [
+                // 1.3.6.1.4.1.11129.2.1.30
+                0x30, 0x1a, 0x06, 0x0a, 0x2b, 0x06, 0x01, 0x04, 0x01, 0xd6, 0x79, 0x02, 0x01, 0x1e,
+                0x04, 0x0c, 0xa2, 0x01, 0x18, 0x20, 0x03, 0x66, 0x47, 0x6f, 0x6f, 0x67, 0x6c, 0x65,
+                // 1.3.6.1.4.1.55555.1.2, critical
+                0x30, 0x16, 0x06, 0x0a, 0x2b, 0x06, 0x01, 0x04, 0x01, 0x83, 0xb2, 0x03, 0x01, 0x02,
+                0x01, 0x01, 0xff, 0x04, 0x05, 0x04, 0x03, 0x61, 0x62, 0x63,
+            ]
+        );
+        assert_eq!(parsed_cert.extra_extension_len, 52);
+        assert_eq!(parsed_cert.extra_extension_offsets, [0, 28]);
+    }
+
+    #[test]
+    fn test_parse_known_unmodelled_extensions() {
+        let cert = include_bytes!("../test_data/es256_ca_known_ext.der");
+        let parsed_cert = ParsedCert::from_der(cert).unwrap();
+
+        // CRL distribution points, certificate policies and an unsupported extension
+        assert_eq!(parsed_cert.extra_extension_offsets, [0, 50, 74]);
+        assert_eq!(parsed_cert.extra_extension_len, 102);
+        assert_eq!(
+            parsed_cert.extra_extension[..9],
+            [0x30, 0x30, 0x06, 0x03, 0x55, 0x1d, 0x1f, 0x04, 0x29]
+        );
+        assert_eq!(
+            parsed_cert.extra_extension[50..59],
+            [0x30, 0x16, 0x06, 0x03, 0x55, 0x1d, 0x20, 0x04, 0x0f]
+        );
+
+        // every extension is re-emitted byte for byte
+        let extensions = &parsed_cert.extra_extension;
+        assert!(cert.windows(extensions.len()).any(|w| w == extensions));
+    }
+
+    #[test]
+    fn test_parse_key_usage() {
+        let cert = include_bytes!("../test_data/es256_ca_crl_sign.der");
+        let parsed_cert = ParsedCert::from_der(cert).unwrap();
+        assert_eq!(parsed_cert.key_usage_index, 4);
+        assert!(parsed_cert.key_usage_critical);
+        assert_eq!(parsed_cert.key_usage, 0x60); // keyCertSign | cRLSign
+        assert_eq!(parsed_cert.extra_extension_len, 0);
+    }
+
+    #[test]
+    fn test_parse_basic_constraints() {
+        let cert = include_bytes!("../test_data/es256_ca_path_len.der");
+        let parsed_cert = ParsedCert::from_der(cert).unwrap();
+        assert_eq!(parsed_cert.basic_constraints_index, 3);
+        assert!(!parsed_cert.basic_constraints_critical);
+        assert!(parsed_cert.basic_constraints_ca);
+        assert_eq!(parsed_cert.basic_constraints_path_len, Some(0));
+        assert_eq!(parsed_cert.extra_extension_len, 0);
+        assert_eq!(parsed_cert.subject_attrs().path_len, Some(0));
+    }
+
+    #[test]
+    fn test_parse_generalized_time() {
+        // notAfter 99991231235959Z
+        let cert = include_bytes!("../test_data/es256_ee_no_expiry.der");
+        let parsed_cert = ParsedCert::from_der(cert).unwrap();
+        assert_eq!(parsed_cert.not_before, [0x07, 0xe9, 1, 1, 0, 0, 0]);
+        assert!(!parsed_cert.not_before_generalized);
+        assert_eq!(parsed_cert.not_after, NO_WELL_DEFINED_EXPIRATION);
+        assert!(parsed_cert.not_after_generalized);
+        assert!(!parsed_cert.has_expiration());
+    }
+
+    #[test]
+    fn test_serialize_basic_constraints() {
+        let bc = |ca, path_len_constraint| BasicConstraints {
+            ca,
+            path_len_constraint,
+        };
+        assert_eq!(
+            serialize_basic_constraints(&bc(true, None)),
+            [0x30, 0x03, 0x01, 0x01, 0xff]
+        );
+        assert_eq!(
+            serialize_basic_constraints(&bc(true, Some(2))),
+            [0x30, 0x06, 0x01, 0x01, 0xff, 0x02, 0x01, 0x02]
+        );
+        assert_eq!(serialize_basic_constraints(&bc(false, None)), [0x30, 0x00]);
+    }
+
+    #[test]
+    fn test_serialize_key_usage() {
+        assert_eq!(serialize_key_usage(0x01), [0x03, 0x02, 0x07, 0x80]);
+        assert_eq!(serialize_key_usage(0x20), [0x03, 0x02, 0x02, 0x04]);
+        assert_eq!(serialize_key_usage(0x60), [0x03, 0x02, 0x01, 0x06]);
+        assert_eq!(serialize_key_usage(0x111), [0x03, 0x03, 0x07, 0x88, 0x80]);
+        assert_eq!(serialize_key_usage(0), [0x03, 0x01, 0x00]);
+    }
+
+    #[test]
+    fn test_subject_attrs_from_der() {
+        let cert = include_bytes!("../test_data/es256_ca.der");
+        let attrs = SubjectAttrs::from_der(cert).unwrap();
+        let parsed_cert = ParsedCert::from_der(cert).unwrap();
+        assert_eq!(attrs.subject, parsed_cert.subject);
+        assert_eq!(attrs.subject_len, 65);
+        assert_eq!(
+            attrs.subject_key_identifier,
+            parsed_cert.subject_key_identifier
+        );
+        assert_eq!(attrs.subject_pk_x, parsed_cert.subject_pk_x);
+        assert_eq!(attrs.subject_pk_y, parsed_cert.subject_pk_y);
+        assert!(attrs.is_ca);
+
+        let cert = include_bytes!("../test_data/es256_ee.der");
+        let attrs = SubjectAttrs::from_der(cert).unwrap();
+        assert_eq!(attrs.subject_key_identifier, [0u8; 20]);
+        assert!(!attrs.is_ca);
+    }
+
+    #[test]
+    fn test_extract_ecdsa_der() {
+        // high-s signature as returned by Android KeyStore
+        let der = hex::decode(
+            "304502207d6ebb5b18133455a59605182df952b0cc213130a5f6d91502be52ddc7fd1097022100dc6cbd\
+             8777813e73b5bec7c7e70451c8d7742599fe646cc99f82a9850462375b",
+        )
+        .unwrap();
+        assert_eq!(
+            hex::encode(extract_ecdsa_der(&der).unwrap()),
+            "7d6ebb5b18133455a59605182df952b0cc213130a5f6d91502be52ddc7fd1097\
+             23934277887ec18d4a41383818fbae36e572d513a8b331bb5437213df800edf6"
+        );
+
+        // short r is left-padded
+        let der = hex::decode("300702020100020101").unwrap();
+        let mut expected = [0u8; 64];
+        expected[30] = 1;
+        expected[63] = 1;
+        assert_eq!(extract_ecdsa_der(&der).unwrap(), expected);
+
+        // r equal to the group order is out of range
+        let der = hex::decode(
+            "3026022100ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632551020101",
+        )
+        .unwrap();
+        assert!(extract_ecdsa_der(&der).is_err());
     }
 }
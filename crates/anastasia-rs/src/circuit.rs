@@ -1,17 +1,29 @@
 use std::{
-    fs::File,
-    io::Read,
+    borrow::Cow,
+    fs,
+    io::{self, Read, Write},
+    path::PathBuf,
     sync::{LazyLock, Mutex},
 };
 
+use ark_std::rand::{RngCore, rngs::OsRng};
+use flate2::read::GzDecoder;
 use noir::barretenberg::{srs::setup_srs, utils::get_circuit_size};
 use serde_json::Value;
 
+use crate::public_inputs::PublicInputsLayout;
+
+/// Where to load a circuit from. Each artifact is read from its path unless given in memory
+/// (e.g. from Android assets or `include_bytes!`), and may be gzip-compressed either way.
+#[derive(Clone)]
 pub struct CircuitMeta {
     pub id: String,
     pub circuit_path: String,
     pub verification_key_path: String,
     pub srs_path: String,
+    pub circuit_bytes: Option<Vec<u8>>,
+    pub verification_key_bytes: Option<Vec<u8>>,
+    pub srs_bytes: Option<Vec<u8>>,
 }
 
 impl CircuitMeta {
@@ -26,34 +38,133 @@ impl CircuitMeta {
             circuit_path,
             verification_key_path,
             srs_path,
+            circuit_bytes: None,
+            verification_key_bytes: None,
+            srs_bytes: None,
         })
     }
+
+    /// Circuit whose JSON, verification key and SRS are all given in memory
+    pub fn from_bytes(
+        id: String,
+        circuit: Vec<u8>,
+        verification_key: Vec<u8>,
+        srs: Vec<u8>,
+    ) -> Self {
+        CircuitMeta {
+            id,
+            circuit_path: String::new(),
+            verification_key_path: String::new(),
+            srs_path: String::new(),
+            circuit_bytes: Some(circuit),
+            verification_key_bytes: Some(verification_key),
+            srs_bytes: Some(srs),
+        }
+    }
+}
+
+impl std::fmt::Debug for CircuitMeta {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let len = |bytes: &Option<Vec<u8>>| bytes.as_ref().map(Vec::len);
+        f.debug_struct("CircuitMeta")
+            .field("id", &self.id)
+            .field("circuit_path", &self.circuit_path)
+            .field("verification_key_path", &self.verification_key_path)
+            .field("srs_path", &self.srs_path)
+            .field("circuit_bytes", &len(&self.circuit_bytes))
+            .field("verification_key_bytes", &len(&self.verification_key_bytes))
+            .field("srs_bytes", &len(&self.srs_bytes))
+            .finish()
+    }
+}
+
+/// SRS to set up, read from a file or given in memory
+#[derive(Clone, Copy)]
+enum SrsSource<'a> {
+    Path(&'a str),
+    Bytes(&'a [u8]),
 }
 
 pub struct Circuit {
     pub id: String,
+    /// Version of the Noir compiler that built the circuit, if recorded
+    pub noir_version: Option<String>,
     pub bytecode: String,
     pub verification_key: Vec<u8>,
     pub circuit_size: u32,
     pub public_input_size: Option<u64>,
     pub max_extra_extension_len: usize,
+    pub max_extra_extension_count: usize,
+    /// Length of `anchor_path` if the circuit proves anchor-set membership
+    pub anchor_set_depth: Option<usize>,
+    /// Length of `revocation_path` if the circuit proves non-revocation
+    pub revocation_tree_depth: Option<usize>,
+    /// Optional public inputs, derived from the public parameters in the circuit ABI
+    pub public_inputs_layout: PublicInputsLayout,
 }
 
 impl Circuit {
     pub fn new(circuit_meta: &CircuitMeta) -> Result<Self, String> {
-        let mut circuit_file =
-            File::open(&circuit_meta.circuit_path).expect("Failed to open JSON file");
-        let mut contents = String::new();
-        circuit_file
-            .read_to_string(&mut contents)
-            .expect("Failed to read JSON file");
-        let v: Value = serde_json::from_str(&contents).expect("Failed to parse JSON");
+        let circuit_json = read_artifact(
+            circuit_meta.circuit_bytes.as_deref(),
+            &circuit_meta.circuit_path,
+        )?;
+        let verification_key = read_artifact(
+            circuit_meta.verification_key_bytes.as_deref(),
+            &circuit_meta.verification_key_path,
+        )?;
+        let srs = match &circuit_meta.srs_bytes {
+            Some(srs) => SrsSource::Bytes(srs),
+            None => SrsSource::Path(&circuit_meta.srs_path),
+        };
+        Self::load(&circuit_meta.id, &circuit_json, verification_key, srs)
+    }
+
+    /// Loads a circuit from its JSON, verification key and SRS held in memory, each optionally
+    /// gzip-compressed.
+    pub fn from_bytes(
+        id: &str,
+        circuit_json: &[u8],
+        verification_key: &[u8],
+        srs: &[u8],
+    ) -> Result<Self, String> {
+        Self::load(
+            id,
+            &decompress(circuit_json)?,
+            decompress(verification_key)?.into_owned(),
+            SrsSource::Bytes(srs),
+        )
+    }
+
+    fn load(
+        id: &str,
+        circuit_json: &[u8],
+        verification_key: Vec<u8>,
+        srs: SrsSource,
+    ) -> Result<Self, String> {
+        let v: Value = serde_json::from_slice(circuit_json)
+            .map_err(|e| format!("Failed to parse circuit `{}`: {}", id, e))?;
 
         let bytecode = v["bytecode"]
             .as_str()
-            .expect("No 'bytecode' property found")
+            .ok_or_else(|| format!("Circuit `{}` has no bytecode", id))?
             .to_string();
 
+        // the witness follows the `main` parameters of the current circuits, which older
+        // artifacts would read in the wrong order
+        if let Some(name) = CURRENT_ABI_PARAMETERS
+            .iter()
+            .find(|name| parameter(&v, name).is_none())
+        {
+            return Err(format!(
+                "Circuit `{}` has no `{}` parameter, so it was compiled from older sources; \
+                 rebuild it with circuits/build.sh",
+                id, name
+            ));
+        }
+
+        let noir_version = v["noir_version"].as_str().map(str::to_string);
+
         let circuit_size = v["circuit_size"]
             .as_u64()
             .map(|v| v as u32)
@@ -66,29 +177,92 @@ impl Circuit {
             .map(|v| v as usize)
             .unwrap_or(128);
 
-        setup_srs_from_bytecode_cached(circuit_size, &circuit_meta.srs_path)?;
+        let max_extra_extension_count = v["max_extra_extension_count"]
+            .as_u64()
+            .map(|v| v as usize)
+            .unwrap_or(4);
+
+        let anchor_set_depth = parameter_length(&v, "anchor_path");
+        let revocation_tree_depth = parameter_length(&v, "revocation_path");
+
+        let public_inputs_layout = PublicInputsLayout {
+            now_granularity: has_public_parameter(&v, "now_granularity"),
+            anchor_set: has_public_parameter(&v, "anchor_root"),
+            path_len: has_public_parameter(&v, "path_len"),
+            revocation: has_public_parameter(&v, "revocation_root"),
+            attestation: has_public_parameter(&v, "attestation_security_level"),
+            nym: has_public_parameter(&v, "nym"),
+            nullifier: has_public_parameter(&v, "nullifier"),
+            possession: has_public_parameter(&v, "pop_challenge"),
+        };
 
-        let mut vk_file =
-            File::open(&circuit_meta.verification_key_path).expect("Failed to open VK file");
-        let mut vk_contents = Vec::new();
-        vk_file
-            .read_to_end(&mut vk_contents)
-            .expect("Failed to read VK file");
+        setup_srs_from_bytecode_cached(circuit_size, srs)?;
 
         Ok(Self {
-            id: circuit_meta.id.clone(),
+            id: id.to_string(),
+            noir_version,
             bytecode,
             circuit_size,
             public_input_size,
             max_extra_extension_len,
-            verification_key: vk_contents,
+            max_extra_extension_count,
+            anchor_set_depth,
+            revocation_tree_depth,
+            public_inputs_layout,
+            verification_key,
         })
     }
 }
 
+/// Parameters every circuit compiled from the current sources takes
+const CURRENT_ABI_PARAMETERS: [&str; 2] = ["extra_extension_offsets", "now_granularity"];
+
+fn parameter<'a>(circuit_json: &'a Value, name: &str) -> Option<&'a Value> {
+    circuit_json["abi"]["parameters"]
+        .as_array()?
+        .iter()
+        .find(|p| p["name"] == name)
+}
+
+fn has_public_parameter(circuit_json: &Value, name: &str) -> bool {
+    parameter(circuit_json, name).is_some_and(|p| p["visibility"] == "public")
+}
+
+/// Length of the array parameter `name` in the circuit ABI
+fn parameter_length(circuit_json: &Value, name: &str) -> Option<usize> {
+    parameter(circuit_json, name)?["type"]["length"]
+        .as_u64()
+        .map(|len| len as usize)
+}
+
 pub static GLOBAL_SRS: LazyLock<Mutex<Option<u32>>> = LazyLock::new(|| Mutex::new(None));
 
-fn setup_srs_from_bytecode_cached(circuit_size: u32, srs_path: &str) -> Result<(), String> {
+/// Names [`create_temp_file`] tries before giving up
+const TEMP_FILE_ATTEMPTS: usize = 8;
+
+/// Reads an artifact from `path` unless given as `bytes`, decompressing it if gzipped
+fn read_artifact(bytes: Option<&[u8]>, path: &str) -> Result<Vec<u8>, String> {
+    let contents = match bytes {
+        Some(bytes) => Cow::Borrowed(bytes),
+        None => Cow::Owned(fs::read(path).map_err(|e| format!("Failed to read {}: {}", path, e))?),
+    };
+    Ok(decompress(&contents)?.into_owned())
+}
+
+/// `bytes` decompressed if they start with the gzip magic number, which neither circuit JSON nor
+/// verification keys and SRS do.
+fn decompress(bytes: &[u8]) -> Result<Cow<'_, [u8]>, String> {
+    if !bytes.starts_with(&[0x1f, 0x8b]) {
+        return Ok(Cow::Borrowed(bytes));
+    }
+    let mut decompressed = Vec::new();
+    GzDecoder::new(bytes)
+        .read_to_end(&mut decompressed)
+        .map_err(|e| format!("Failed to decompress: {}", e))?;
+    Ok(Cow::Owned(decompressed))
+}
+
+fn setup_srs_from_bytecode_cached(circuit_size: u32, srs: SrsSource) -> Result<(), String> {
     let mut cache = GLOBAL_SRS.lock().unwrap();
 
     let need_reinit = match &*cache {
@@ -97,19 +271,72 @@ fn setup_srs_from_bytecode_cached(circuit_size: u32, srs_path: &str) -> Result<(
     };
 
     if need_reinit {
-        println!(
-            "Setting up SRS for circuit size {} with path {:?}",
-            circuit_size, srs_path
-        );
-        setup_srs(circuit_size, Some(srs_path)).map(|_| ())?;
+        match srs {
+            SrsSource::Path(srs_path) => {
+                setup_srs(circuit_size, Some(srs_path)).map(|_| ())?;
+            }
+            SrsSource::Bytes(srs) => setup_srs_from_bytes(circuit_size, srs)?,
+        }
         *cache = Some(circuit_size);
     }
     Ok(())
 }
 
+/// `setup_srs` only reads the SRS from a file, so the bytes are written to a new temporary file
+/// under [`std::env::temp_dir`], which is removed once the SRS is set up. On Android, `TMPDIR`
+/// must point to a writable directory such as the app's cache directory.
+#[cfg(not(target_arch = "wasm32"))]
+fn setup_srs_from_bytes(circuit_size: u32, srs: &[u8]) -> Result<(), String> {
+    let srs = decompress(srs)?;
+    let (path, mut file) = create_temp_file("srs")?;
+    let result = file
+        .write_all(&srs)
+        .and_then(|_| file.sync_all())
+        .map_err(|e| format!("Failed to write SRS to {}: {}", path.display(), e))
+        .and_then(|_| match path.to_str() {
+            Some(srs_path) => setup_srs(circuit_size, Some(srs_path)).map(|_| ()),
+            None => Err(format!("Invalid SRS path {}", path.display())),
+        });
+    drop(file);
+    let _ = fs::remove_file(&path);
+    result
+}
+
+/// There is no file system to hand the SRS to `setup_srs` through on wasm32.
+#[cfg(target_arch = "wasm32")]
+fn setup_srs_from_bytes(_circuit_size: u32, _srs: &[u8]) -> Result<(), String> {
+    Err("Setting up the SRS from bytes is not supported on wasm32".to_string())
+}
+
+/// Creates a file with a random name under [`std::env::temp_dir`], readable only by the current
+/// user on Unix. It fails rather than opening an existing file or symlink of the same name.
+#[cfg(not(target_arch = "wasm32"))]
+fn create_temp_file(extension: &str) -> Result<(PathBuf, fs::File), String> {
+    let dir = std::env::temp_dir();
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    for _ in 0..TEMP_FILE_ATTEMPTS {
+        let path = dir.join(format!("anastasia-{:016x}.{}", OsRng.next_u64(), extension));
+        match options.open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(format!("Failed to create {}: {}", path.display(), e)),
+        }
+    }
+    Err(format!(
+        "Failed to create a temporary file in {}",
+        dir.display()
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use flate2::{Compression, write::GzEncoder};
+    use std::io::Write;
 
     #[test]
     fn test_circuit_meta_new() {
@@ -137,10 +364,122 @@ mod tests {
         .unwrap();
         let circuit = Circuit::new(&meta).unwrap();
         assert_eq!(circuit.id, "es256_ca");
+        assert!(
+            circuit
+                .noir_version
+                .as_deref()
+                .is_some_and(|v| v.starts_with("1.0.0-beta.8"))
+        );
         assert!(!circuit.bytecode.is_empty());
         assert!(!circuit.verification_key.is_empty());
         assert!(circuit.circuit_size > 0);
         //assert!(circuit.public_input_size.is_some());
         assert!(circuit.max_extra_extension_len > 0);
+        assert!(circuit.max_extra_extension_count > 0);
+        assert_eq!(
+            circuit.public_inputs_layout,
+            PublicInputsLayout {
+                now_granularity: true,
+                path_len: true,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_circuit_from_bytes() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(&fs::read("data/es256_ca.json").unwrap())
+            .unwrap();
+        let circuit_json = encoder.finish().unwrap();
+        let verification_key = fs::read("data/es256_ca.vk").unwrap();
+        let srs = fs::read("data/common.srs").unwrap();
+
+        let circuit =
+            Circuit::from_bytes("es256_ca", &circuit_json, &verification_key, &srs).unwrap();
+        assert_eq!(circuit.verification_key, verification_key);
+        let meta =
+            CircuitMeta::from_bytes("es256_ca".to_string(), circuit_json, verification_key, srs);
+        assert_eq!(Circuit::new(&meta).unwrap().bytecode, circuit.bytecode);
+    }
+
+    #[test]
+    fn test_circuit_rejects_stale_abi() {
+        let circuit_json = br#"{"bytecode": "", "abi": {"parameters": [
+            {"name": "extra_extension_len", "visibility": "private"},
+            {"name": "now", "visibility": "public"}
+        ]}}"#;
+        let err = Circuit::from_bytes("es256_ca", circuit_json, b"", b"")
+            .err()
+            .unwrap();
+        assert!(err.contains("circuits/build.sh"), "{}", err);
+    }
+
+    #[test]
+    fn test_read_artifact() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"{\"bytecode\": \"\"}").unwrap();
+        let compressed = encoder.finish().unwrap();
+        assert_eq!(
+            read_artifact(Some(&compressed), "").unwrap(),
+            b"{\"bytecode\": \"\"}"
+        );
+        assert_eq!(read_artifact(Some(b"\x00\x01"), "").unwrap(), b"\x00\x01");
+        assert_eq!(
+            read_artifact(None, "data/es256_ca.vk").unwrap(),
+            fs::read("data/es256_ca.vk").unwrap()
+        );
+        assert!(read_artifact(None, "data/missing.vk").is_err());
+        assert!(read_artifact(Some(&compressed[..compressed.len() / 2]), "").is_err());
+    }
+
+    #[test]
+    fn test_create_temp_file() {
+        let (path, _file) = create_temp_file("srs").unwrap();
+        let (other_path, _other_file) = create_temp_file("srs").unwrap();
+        assert_ne!(path, other_path);
+        assert!(path.starts_with(std::env::temp_dir()));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        fs::remove_file(path).unwrap();
+        fs::remove_file(other_path).unwrap();
+    }
+
+    #[test]
+    fn test_has_public_parameter() {
+        let v: Value = serde_json::from_str(
+            r#"{"abi": {"parameters": [
+                {"name": "next_cmt_r", "visibility": "private"},
+                {"name": "attestation_security_level", "visibility": "public"}
+            ]}}"#,
+        )
+        .unwrap();
+        assert!(has_public_parameter(&v, "attestation_security_level"));
+        assert!(!has_public_parameter(&v, "next_cmt_r"));
+        assert!(!has_public_parameter(&v, "device_locked"));
+        assert!(!has_public_parameter(
+            &Value::Null,
+            "attestation_security_level"
+        ));
+    }
+
+    #[test]
+    fn test_parameter_length() {
+        let v: Value = serde_json::from_str(
+            r#"{"abi": {"parameters": [
+                {"name": "anchor_root", "type": {"kind": "field"}, "visibility": "public"},
+                {"name": "anchor_path", "type": {"kind": "array", "length": 4,
+                    "type": {"kind": "field"}}, "visibility": "private"}
+            ]}}"#,
+        )
+        .unwrap();
+        assert_eq!(parameter_length(&v, "anchor_path"), Some(4));
+        assert_eq!(parameter_length(&v, "anchor_root"), None);
+        assert_eq!(parameter_length(&v, "revocation_path"), None);
     }
 }
@@ -0,0 +1,575 @@
+//! Android Key Attestation extension (OID 1.3.6.1.4.1.11129.2.1.17).
+//!
+//! See https://source.android.com/docs/security/features/keystore/attestation#schema.
+//! Every parsed field records the offset of its DER TLV, relative to the first byte of the
+//! `Extension` as serialized in [`ParsedCert::extra_extension`](crate::cert::ParsedCert).
+//! For fields of an AuthorizationList, this is the TLV inside the explicit tag.
+
+use crate::cert::ParsedCert;
+
+/// DER encoding of 1.3.6.1.4.1.11129.2.1.17
+pub const KEY_DESCRIPTION_OID: [u8; 10] =
+    [0x2b, 0x06, 0x01, 0x04, 0x01, 0xd6, 0x79, 0x02, 0x01, 0x11];
+
+const TAG_INTEGER: u32 = 0x02;
+const TAG_OCTET_STRING: u32 = 0x04;
+const TAG_BOOLEAN: u32 = 0x01;
+const TAG_NULL: u32 = 0x05;
+const TAG_ENUMERATED: u32 = 0x0a;
+const TAG_SEQUENCE: u32 = 0x10;
+const TAG_SET: u32 = 0x11;
+const TAG_OID: u32 = 0x06;
+
+// AuthorizationList tags
+const KM_TAG_PURPOSE: u32 = 1;
+const KM_TAG_ALGORITHM: u32 = 2;
+const KM_TAG_KEY_SIZE: u32 = 3;
+const KM_TAG_DIGEST: u32 = 5;
+const KM_TAG_EC_CURVE: u32 = 10;
+const KM_TAG_NO_AUTH_REQUIRED: u32 = 503;
+pub(crate) const KM_TAG_CREATION_DATETIME: u32 = 701;
+const KM_TAG_ORIGIN: u32 = 702;
+const KM_TAG_ROOT_OF_TRUST: u32 = 704;
+pub(crate) const KM_TAG_OS_VERSION: u32 = 705;
+pub(crate) const KM_TAG_OS_PATCHLEVEL: u32 = 706;
+const KM_TAG_ATTESTATION_APPLICATION_ID: u32 = 709;
+pub(crate) const KM_TAG_VENDOR_PATCHLEVEL: u32 = 718;
+pub(crate) const KM_TAG_BOOT_PATCHLEVEL: u32 = 719;
+
+/// A parsed value together with the offset of its DER TLV.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Located<T> {
+    pub value: T,
+    pub offset: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecurityLevel {
+    Software = 0,
+    TrustedEnvironment = 1,
+    StrongBox = 2,
+}
+
+impl SecurityLevel {
+    pub fn from_u64(v: u64) -> Result<Self, String> {
+        match v {
+            0 => Ok(SecurityLevel::Software),
+            1 => Ok(SecurityLevel::TrustedEnvironment),
+            2 => Ok(SecurityLevel::StrongBox),
+            _ => Err(format!("Unknown SecurityLevel {}", v)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifiedBootState {
+    Verified = 0,
+    SelfSigned = 1,
+    Unverified = 2,
+    Failed = 3,
+}
+
+impl VerifiedBootState {
+    pub fn from_u64(v: u64) -> Result<Self, String> {
+        match v {
+            0 => Ok(VerifiedBootState::Verified),
+            1 => Ok(VerifiedBootState::SelfSigned),
+            2 => Ok(VerifiedBootState::Unverified),
+            3 => Ok(VerifiedBootState::Failed),
+            _ => Err(format!("Unknown VerifiedBootState {}", v)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RootOfTrust {
+    pub offset: u32,
+    pub verified_boot_key: Located<Vec<u8>>,
+    pub device_locked: Located<bool>,
+    pub verified_boot_state: Located<VerifiedBootState>,
+    /// Since attestation version 3
+    pub verified_boot_hash: Option<Located<Vec<u8>>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttestationPackageInfo {
+    pub offset: u32,
+    pub package_name: Located<Vec<u8>>,
+    pub version: Located<u64>,
+}
+
+/// The apps sharing the attested key's UID and the digests of their signing certificates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttestationApplicationId {
+    /// Offset of the OCTET STRING wrapping the DER AttestationApplicationId
+    pub offset: u32,
+    pub package_infos: Vec<AttestationPackageInfo>,
+    /// SHA-256 digests
+    pub signature_digests: Vec<Located<Vec<u8>>>,
+}
+
+/// The AuthorizationList tags Anastasia uses; other tags are skipped.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuthorizationList {
+    pub offset: u32,
+    pub purpose: Option<Located<Vec<u64>>>,
+    pub algorithm: Option<Located<u64>>,
+    pub key_size: Option<Located<u64>>,
+    pub digest: Option<Located<Vec<u64>>>,
+    pub ec_curve: Option<Located<u64>>,
+    pub no_auth_required: bool,
+    /// Milliseconds since the Unix epoch
+    pub creation_date_time: Option<Located<u64>>,
+    pub origin: Option<Located<u64>>,
+    pub root_of_trust: Option<RootOfTrust>,
+    /// e.g. 160000 for Android 16
+    pub os_version: Option<Located<u64>>,
+    /// YYYYMM
+    pub os_patch_level: Option<Located<u64>>,
+    pub attestation_application_id: Option<AttestationApplicationId>,
+    /// YYYYMMDD
+    pub vendor_patch_level: Option<Located<u64>>,
+    /// YYYYMMDD
+    pub boot_patch_level: Option<Located<u64>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyDescription {
+    pub attestation_version: Located<u64>,
+    pub attestation_security_level: Located<SecurityLevel>,
+    pub key_mint_version: Located<u64>,
+    pub key_mint_security_level: Located<SecurityLevel>,
+    pub attestation_challenge: Located<Vec<u8>>,
+    pub unique_id: Located<Vec<u8>>,
+    pub software_enforced: AuthorizationList,
+    pub hardware_enforced: AuthorizationList,
+}
+
+impl KeyDescription {
+    /// Parses a DER `Extension` carrying a KeyDescription, e.g. a slice of
+    /// `ParsedCert::extra_extension` starting at one of its offsets.
+    pub fn from_extension(ext: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader::new(ext, 0);
+        let extension = reader.read_tlv()?.expect_universal(TAG_SEQUENCE)?;
+
+        let mut reader = extension.contents(ext);
+        let oid = reader.read_tlv()?.expect_universal(TAG_OID)?;
+        if oid.value(ext) != KEY_DESCRIPTION_OID {
+            return Err("Extension is not a KeyDescription".to_string());
+        }
+        let mut value = reader.read_tlv()?;
+        if value.is_universal(TAG_BOOLEAN) {
+            value = reader.read_tlv()?; // critical
+        }
+        let value = value.expect_universal(TAG_OCTET_STRING)?;
+
+        let mut reader = value.contents(ext);
+        let key_description = reader.read_tlv()?.expect_universal(TAG_SEQUENCE)?;
+        let mut reader = key_description.contents(ext);
+
+        let attestation_version = reader.read_integer(ext)?;
+        let attestation_security_level = reader.read_security_level(ext)?;
+        let key_mint_version = reader.read_integer(ext)?;
+        let key_mint_security_level = reader.read_security_level(ext)?;
+        let attestation_challenge = reader.read_octet_string(ext)?;
+        let unique_id = reader.read_octet_string(ext)?;
+        let software_enforced = parse_authorization_list(ext, reader.read_tlv()?)?;
+        let hardware_enforced = parse_authorization_list(ext, reader.read_tlv()?)?;
+
+        Ok(KeyDescription {
+            attestation_version,
+            attestation_security_level,
+            key_mint_version,
+            key_mint_security_level,
+            attestation_challenge,
+            unique_id,
+            software_enforced,
+            hardware_enforced,
+        })
+    }
+}
+
+impl ParsedCert {
+    /// Offset of the Android Key Attestation extension within `extra_extension`.
+    pub fn key_description_offset(&self) -> Option<u32> {
+        self.extra_extension_offsets.iter().copied().find(|offset| {
+            let mut reader = Reader::new(&self.extra_extension, *offset as usize);
+            let oid = reader
+                .read_tlv()
+                .and_then(|ext| ext.contents(&self.extra_extension).read_tlv());
+            matches!(oid, Ok(oid) if oid.value(&self.extra_extension) == KEY_DESCRIPTION_OID)
+        })
+    }
+
+    pub fn key_description(&self) -> Result<Option<KeyDescription>, String> {
+        self.key_description_offset()
+            .map(|offset| KeyDescription::from_extension(&self.extra_extension[offset as usize..]))
+            .transpose()
+    }
+}
+
+fn parse_authorization_list(bytes: &[u8], list: Tlv) -> Result<AuthorizationList, String> {
+    let list = list.expect_universal(TAG_SEQUENCE)?;
+    let mut auth_list = AuthorizationList {
+        offset: list.offset as u32,
+        ..Default::default()
+    };
+
+    let mut reader = list.contents(bytes);
+    while !reader.is_empty() {
+        let tagged = reader.read_tlv()?;
+        if tagged.class != CLASS_CONTEXT_SPECIFIC || !tagged.constructed {
+            return Err("AuthorizationList entries must be explicitly tagged".to_string());
+        }
+        let mut inner = tagged.contents(bytes);
+        match tagged.tag {
+            KM_TAG_PURPOSE => auth_list.purpose = Some(inner.read_integer_set(bytes)?),
+            KM_TAG_ALGORITHM => auth_list.algorithm = Some(inner.read_integer(bytes)?),
+            KM_TAG_KEY_SIZE => auth_list.key_size = Some(inner.read_integer(bytes)?),
+            KM_TAG_DIGEST => auth_list.digest = Some(inner.read_integer_set(bytes)?),
+            KM_TAG_EC_CURVE => auth_list.ec_curve = Some(inner.read_integer(bytes)?),
+            KM_TAG_NO_AUTH_REQUIRED => {
+                inner.read_tlv()?.expect_universal(TAG_NULL)?;
+                auth_list.no_auth_required = true;
+            }
+            KM_TAG_CREATION_DATETIME => {
+                auth_list.creation_date_time = Some(inner.read_integer(bytes)?)
+            }
+            KM_TAG_ORIGIN => auth_list.origin = Some(inner.read_integer(bytes)?),
+            KM_TAG_ROOT_OF_TRUST => {
+                auth_list.root_of_trust = Some(parse_root_of_trust(bytes, inner.read_tlv()?)?)
+            }
+            KM_TAG_OS_VERSION => auth_list.os_version = Some(inner.read_integer(bytes)?),
+            KM_TAG_OS_PATCHLEVEL => auth_list.os_patch_level = Some(inner.read_integer(bytes)?),
+            KM_TAG_ATTESTATION_APPLICATION_ID => {
+                auth_list.attestation_application_id =
+                    Some(parse_attestation_application_id(bytes, inner.read_tlv()?)?)
+            }
+            KM_TAG_VENDOR_PATCHLEVEL => {
+                auth_list.vendor_patch_level = Some(inner.read_integer(bytes)?)
+            }
+            KM_TAG_BOOT_PATCHLEVEL => auth_list.boot_patch_level = Some(inner.read_integer(bytes)?),
+            _ => {}
+        }
+    }
+    Ok(auth_list)
+}
+
+fn parse_attestation_application_id(
+    bytes: &[u8],
+    value: Tlv,
+) -> Result<AttestationApplicationId, String> {
+    let value = value.expect_universal(TAG_OCTET_STRING)?;
+    let application_id = value
+        .contents(bytes)
+        .read_tlv()?
+        .expect_universal(TAG_SEQUENCE)?;
+    let mut reader = application_id.contents(bytes);
+
+    let package_infos = reader.read_tlv()?.expect_universal(TAG_SET)?;
+    let mut set = package_infos.contents(bytes);
+    let mut package_infos = Vec::new();
+    while !set.is_empty() {
+        let package_info = set.read_tlv()?.expect_universal(TAG_SEQUENCE)?;
+        let mut reader = package_info.contents(bytes);
+        package_infos.push(AttestationPackageInfo {
+            offset: package_info.offset as u32,
+            package_name: reader.read_octet_string(bytes)?,
+            version: reader.read_integer(bytes)?,
+        });
+    }
+
+    let signature_digests = reader.read_tlv()?.expect_universal(TAG_SET)?;
+    let mut set = signature_digests.contents(bytes);
+    let mut signature_digests = Vec::new();
+    while !set.is_empty() {
+        signature_digests.push(set.read_octet_string(bytes)?);
+    }
+
+    Ok(AttestationApplicationId {
+        offset: value.offset as u32,
+        package_infos,
+        signature_digests,
+    })
+}
+
+fn parse_root_of_trust(bytes: &[u8], root_of_trust: Tlv) -> Result<RootOfTrust, String> {
+    let root_of_trust = root_of_trust.expect_universal(TAG_SEQUENCE)?;
+    let mut reader = root_of_trust.contents(bytes);
+
+    let verified_boot_key = reader.read_octet_string(bytes)?;
+    let device_locked = reader.read_tlv()?.expect_universal(TAG_BOOLEAN)?;
+    let device_locked = Located {
+        value: match device_locked.value(bytes) {
+            [0x00] => false,
+            [0xff] => true,
+            _ => return Err("Invalid BOOLEAN".to_string()),
+        },
+        offset: device_locked.offset as u32,
+    };
+    let verified_boot_state = reader.read_enumerated(bytes)?;
+    let verified_boot_state = Located {
+        value: VerifiedBootState::from_u64(verified_boot_state.value)?,
+        offset: verified_boot_state.offset,
+    };
+    let verified_boot_hash = match reader.is_empty() {
+        true => None,
+        false => Some(reader.read_octet_string(bytes)?),
+    };
+
+    Ok(RootOfTrust {
+        offset: root_of_trust.offset as u32,
+        verified_boot_key,
+        device_locked,
+        verified_boot_state,
+        verified_boot_hash,
+    })
+}
+
+const CLASS_UNIVERSAL: u8 = 0;
+const CLASS_CONTEXT_SPECIFIC: u8 = 2;
+
+/// A DER TLV; offsets index the buffer it was read from.
+#[derive(Debug, Clone, Copy)]
+struct Tlv {
+    class: u8,
+    constructed: bool,
+    tag: u32,
+    offset: usize,
+    value_offset: usize,
+    end: usize,
+}
+
+impl Tlv {
+    fn is_universal(&self, tag: u32) -> bool {
+        self.class == CLASS_UNIVERSAL && self.tag == tag
+    }
+
+    fn expect_universal(self, tag: u32) -> Result<Self, String> {
+        if !self.is_universal(tag) {
+            return Err(format!(
+                "Expected universal tag {} at offset {}, got class {} tag {}",
+                tag, self.offset, self.class, self.tag
+            ));
+        }
+        Ok(self)
+    }
+
+    fn value<'a>(&self, bytes: &'a [u8]) -> &'a [u8] {
+        &bytes[self.value_offset..self.end]
+    }
+
+    fn contents<'a>(&self, bytes: &'a [u8]) -> Reader<'a> {
+        Reader {
+            bytes: &bytes[..self.end],
+            pos: self.value_offset,
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8], pos: usize) -> Self {
+        Reader { bytes, pos }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+
+    fn next_byte(&mut self) -> Result<u8, String> {
+        let b = *self
+            .bytes
+            .get(self.pos)
+            .ok_or_else(|| format!("Unexpected end of DER at offset {}", self.pos))?;
+        self.pos += 1;
+        Ok(b)
+    }
+
+    fn read_tlv(&mut self) -> Result<Tlv, String> {
+        let offset = self.pos;
+        let first = self.next_byte()?;
+        let class = first >> 6;
+        let constructed = first & 0x20 != 0;
+        let mut tag = (first & 0x1f) as u32;
+        if tag == 0x1f {
+            // high tag number form
+            tag = 0;
+            loop {
+                let b = self.next_byte()?;
+                tag = (tag << 7) | (b & 0x7f) as u32;
+                if b & 0x80 == 0 {
+                    break;
+                }
+                if tag > 0xffff {
+                    return Err(format!("Tag too large at offset {}", offset));
+                }
+            }
+        }
+
+        let len = match self.next_byte()? {
+            len @ 0..=0x7f => len as usize,
+            0x81 => self.next_byte()? as usize,
+            0x82 => ((self.next_byte()? as usize) << 8) | self.next_byte()? as usize,
+            _ => return Err(format!("Unsupported length at offset {}", offset)),
+        };
+        let value_offset = self.pos;
+        let end = value_offset + len;
+        if end > self.bytes.len() {
+            return Err(format!("DER length exceeds input at offset {}", offset));
+        }
+        self.pos = end;
+
+        Ok(Tlv {
+            class,
+            constructed,
+            tag,
+            offset,
+            value_offset,
+            end,
+        })
+    }
+
+    fn read_unsigned(&mut self, bytes: &[u8], tag: u32) -> Result<Located<u64>, String> {
+        let tlv = self.read_tlv()?.expect_universal(tag)?;
+        let value = tlv.value(bytes);
+        if value.is_empty() || value[0] & 0x80 != 0 {
+            return Err(format!(
+                "Expected a non-negative integer at offset {}",
+                tlv.offset
+            ));
+        }
+        let value = match value {
+            [0x00, rest @ ..] if !rest.is_empty() => rest,
+            _ => value,
+        };
+        if value.len() > 8 {
+            return Err(format!("Integer too large at offset {}", tlv.offset));
+        }
+        Ok(Located {
+            value: value.iter().fold(0, |acc, b| (acc << 8) | *b as u64),
+            offset: tlv.offset as u32,
+        })
+    }
+
+    fn read_integer(&mut self, bytes: &[u8]) -> Result<Located<u64>, String> {
+        self.read_unsigned(bytes, TAG_INTEGER)
+    }
+
+    fn read_enumerated(&mut self, bytes: &[u8]) -> Result<Located<u64>, String> {
+        self.read_unsigned(bytes, TAG_ENUMERATED)
+    }
+
+    fn read_security_level(&mut self, bytes: &[u8]) -> Result<Located<SecurityLevel>, String> {
+        let level = self.read_enumerated(bytes)?;
+        Ok(Located {
+            value: SecurityLevel::from_u64(level.value)?,
+            offset: level.offset,
+        })
+    }
+
+    fn read_octet_string(&mut self, bytes: &[u8]) -> Result<Located<Vec<u8>>, String> {
+        let tlv = self.read_tlv()?.expect_universal(TAG_OCTET_STRING)?;
+        Ok(Located {
+            value: tlv.value(bytes).to_vec(),
+            offset: tlv.offset as u32,
+        })
+    }
+
+    fn read_integer_set(&mut self, bytes: &[u8]) -> Result<Located<Vec<u64>>, String> {
+        let set = self.read_tlv()?.expect_universal(TAG_SET)?;
+        let mut reader = set.contents(bytes);
+        let mut values = Vec::new();
+        while !reader.is_empty() {
+            values.push(reader.read_integer(bytes)?.value);
+        }
+        Ok(Located {
+            value: values,
+            offset: set.offset as u32,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_key_description() {
+        let cert = include_bytes!("../test_data/es256_ee.der");
+        let parsed_cert = ParsedCert::from_der(cert).unwrap();
+        assert_eq!(parsed_cert.key_description_offset(), Some(0));
+        let kd = parsed_cert.key_description().unwrap().unwrap();
+        let ext = &parsed_cert.extra_extension;
+
+        assert_eq!(kd.attestation_version.value, 300);
+        assert_eq!(kd.attestation_version.offset, 24);
+        assert_eq!(
+            kd.attestation_security_level,
+            Located {
+                value: SecurityLevel::StrongBox,
+                offset: 28
+            }
+        );
+        assert_eq!(kd.key_mint_version.value, 300);
+        assert_eq!(kd.key_mint_security_level.value, SecurityLevel::StrongBox);
+        assert_eq!(kd.attestation_challenge.value, [0x00]);
+        assert_eq!(kd.attestation_challenge.offset, 38);
+        assert!(kd.unique_id.value.is_empty());
+
+        let sw = &kd.software_enforced;
+        assert_eq!(
+            sw.creation_date_time.as_ref().unwrap().value,
+            0x019903ec61b9
+        );
+        let app_id = sw.attestation_application_id.as_ref().unwrap();
+        assert_eq!(app_id.offset, 61);
+        assert_eq!(app_id.package_infos.len(), 1);
+        let package_info = &app_id.package_infos[0];
+        assert_eq!(package_info.offset, 67);
+        assert_eq!(package_info.package_name.value, b"com.example.moproapp");
+        assert_eq!(package_info.version.value, 1);
+        assert_eq!(app_id.signature_digests.len(), 1);
+        assert_eq!(app_id.signature_digests[0].offset, 96);
+        assert_eq!(
+            hex::encode(&app_id.signature_digests[0].value),
+            "a6bfe8e8029aff3be388beb06371ccdf94f801df433d76b7cbedcf4b538dd876"
+        );
+        assert!(sw.root_of_trust.is_none());
+
+        let hw = &kd.hardware_enforced;
+        assert_eq!(hw.purpose.as_ref().unwrap().value, [2, 3]);
+        assert_eq!(hw.algorithm.as_ref().unwrap().value, 3); // EC
+        assert_eq!(hw.key_size.as_ref().unwrap().value, 256);
+        assert_eq!(hw.digest.as_ref().unwrap().value, [4]); // SHA-256
+        assert_eq!(hw.ec_curve.as_ref().unwrap().value, 1); // P-256
+        assert_eq!(hw.origin.as_ref().unwrap().value, 0); // generated
+        assert_eq!(hw.os_version.as_ref().unwrap().value, 160000);
+        assert_eq!(hw.os_patch_level.as_ref().unwrap().value, 202508);
+        assert_eq!(hw.vendor_patch_level.as_ref().unwrap().value, 20250805);
+        assert_eq!(hw.boot_patch_level.as_ref().unwrap().value, 20250805);
+
+        let rot = hw.root_of_trust.as_ref().unwrap();
+        assert_eq!(rot.verified_boot_key.value.len(), 32);
+        assert!(rot.device_locked.value);
+        let offset = rot.device_locked.offset as usize;
+        assert_eq!(ext[offset..offset + 3], [0x01, 0x01, 0xff]);
+        assert_eq!(rot.verified_boot_state.value, VerifiedBootState::Verified);
+        let offset = rot.verified_boot_state.offset as usize;
+        assert_eq!(ext[offset..offset + 3], [0x0a, 0x01, 0x00]);
+        assert_eq!(rot.verified_boot_hash.as_ref().unwrap().value.len(), 32);
+    }
+
+    #[test]
+    fn test_no_key_description() {
+        let cert = include_bytes!("../test_data/es256_ca.der");
+        let parsed_cert = ParsedCert::from_der(cert).unwrap();
+        assert_eq!(parsed_cert.key_description_offset(), None);
+        assert!(parsed_cert.key_description().unwrap().is_none());
+        assert!(KeyDescription::from_extension(&parsed_cert.extra_extension).is_err());
+    }
+}
@@ -1,9 +1,41 @@
+mod anchors;
 mod api;
 mod cert;
 mod circuit;
+mod key_description;
+mod merkle;
+mod nullifier;
 mod poseidon;
+mod predicate;
 mod prove;
+mod prover;
+mod public_inputs;
+mod registry;
+mod revocation;
 mod utils;
+mod verify;
 
-pub use api::{CommitResult, ProofResult, commit_attrs, prove};
+pub use anchors::{AnchorSet, KeyAlgorithm, TrustAnchor, TrustAnchorStore};
+pub use api::{
+    AttestationRequest, ChainLink, ChainProofResult, ChainVerificationError, CommitResult,
+    IssuerInputs, LinkProof, NullifierProofResult, NymProofResult, ProofResult, ProveOptions,
+    VerifiedChain, VerifiedPublicInputs, app_id_hash, commit_anchor, commit_attrs, commit_cert,
+    prove, prove_chain, prove_ee_with_nullifier, prove_ee_with_nym, prove_ee_with_possession,
+    verify, verify_chain, verify_possession,
+};
+pub use cert::{SignatureAlgorithm, SubjectAttrs};
 pub use circuit::{Circuit, CircuitMeta};
+pub use key_description::{
+    AttestationApplicationId, AttestationPackageInfo, AuthorizationList, KEY_DESCRIPTION_OID,
+    KeyDescription, Located, RootOfTrust, SecurityLevel, VerifiedBootState,
+};
+pub use nullifier::{FileNullifierStore, InMemoryNullifierStore, NullifierStore};
+pub use predicate::{Predicate, PredicateAttribute, PredicateResult};
+pub use prover::Prover;
+pub use public_inputs::{
+    AttestationDisclosure, NO_PATH_LEN, Nullifier, Nym, Possession, PublicInputs,
+    PublicInputsLayout, join_proof, split_attested_proof, split_proof, split_proof_with_layout,
+};
+pub use registry::{CircuitDescriptor, CircuitRegistry, CircuitRole, CircuitVariant};
+pub use revocation::{NonRevocationWitness, RevocationList, RevocationTree};
+pub use utils::TimeGranularity;
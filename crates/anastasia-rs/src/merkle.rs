@@ -0,0 +1,133 @@
+use ark_bn254::Fr;
+#[cfg(test)]
+use ark_crypto_primitives::crh::TwoToOneCRHScheme;
+use ark_crypto_primitives::merkle_tree::{self, IdentityDigestConverter};
+
+use crate::{
+    poseidon::{CRH, TwoToOneCRH},
+    utils::POSEIDON_CONFIG_2,
+};
+
+/// Deepest tree [`MerkleTree::new`] builds, since it hashes all `2^depth` leaves
+const MAX_DEPTH: usize = 24;
+
+/// Poseidon nodes as `hash_2` in the circuits. The trees are built from leaf digests, so
+/// `LeafHash` is never evaluated.
+struct PoseidonConfig;
+
+impl merkle_tree::Config for PoseidonConfig {
+    type Leaf = [Fr];
+    type LeafDigest = Fr;
+    type LeafInnerDigestConverter = IdentityDigestConverter<Fr>;
+    type InnerDigest = Fr;
+    type LeafHash = CRH<Fr>;
+    type TwoToOneHash = TwoToOneCRH<Fr>;
+}
+
+/// Poseidon Merkle tree of a fixed depth, whose leaves after the given ones are all `padding`.
+pub(crate) struct MerkleTree {
+    tree: merkle_tree::MerkleTree<PoseidonConfig>,
+    depth: usize,
+}
+
+impl MerkleTree {
+    pub(crate) fn new(mut leaves: Vec<Fr>, padding: Fr, depth: usize) -> Result<Self, String> {
+        if depth == 0 || depth > MAX_DEPTH || leaves.len() > 1 << depth {
+            return Err(format!(
+                "{} leaves do not fit in a tree of depth {}",
+                leaves.len(),
+                depth
+            ));
+        }
+
+        leaves.resize(1 << depth, padding);
+        let tree = merkle_tree::MerkleTree::new_with_leaf_digest(
+            &*POSEIDON_CONFIG_2,
+            &*POSEIDON_CONFIG_2,
+            leaves,
+        )
+        .map_err(|e| format!("Failed to build Merkle tree: {}", e))?;
+        Ok(MerkleTree { tree, depth })
+    }
+
+    pub(crate) fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub(crate) fn root(&self) -> Fr {
+        self.tree.root()
+    }
+
+    /// Siblings of the leaf at `index`, from the leaf up to the root
+    pub(crate) fn path(&self, index: usize) -> Result<Vec<Fr>, String> {
+        let path = self
+            .tree
+            .generate_proof(index)
+            .map_err(|e| format!("Failed to compute Merkle path: {}", e))?;
+        Ok(std::iter::once(path.leaf_sibling_hash)
+            .chain(path.auth_path.into_iter().rev())
+            .collect())
+    }
+}
+
+/// Root of the tree in which `leaf` at `index` has the siblings `path`, as the circuits compute it
+#[cfg(test)]
+pub(crate) fn root_from_path(leaf: Fr, index: u64, path: &[Fr]) -> Result<Fr, String> {
+    let mut node = leaf;
+    for (level, sibling) in path.iter().enumerate() {
+        node = if (index >> level) & 1 == 1 {
+            hash_2(*sibling, node)?
+        } else {
+            hash_2(node, *sibling)?
+        };
+    }
+    Ok(node)
+}
+
+#[cfg(test)]
+fn hash_2(left: Fr, right: Fr) -> Result<Fr, String> {
+    TwoToOneCRH::<Fr>::compress(&*POSEIDON_CONFIG_2, left, right)
+        .map_err(|e| format!("Poseidon CRH error: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merkle_tree() {
+        let leaves: Vec<_> = (1..=3u64).map(Fr::from).collect();
+        let padding = Fr::from(0u64);
+        let tree = MerkleTree::new(leaves.clone(), padding, 2).unwrap();
+        let root = hash_2(
+            hash_2(leaves[0], leaves[1]).unwrap(),
+            hash_2(leaves[2], padding).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(tree.root(), root);
+
+        for (i, leaf) in leaves.iter().enumerate() {
+            let path = tree.path(i).unwrap();
+            assert_eq!(path.len(), 2);
+            assert_eq!(root_from_path(*leaf, i as u64, &path).unwrap(), root);
+        }
+        assert_eq!(
+            root_from_path(padding, 3, &tree.path(3).unwrap()).unwrap(),
+            root
+        );
+        assert_ne!(
+            root_from_path(leaves[0], 1, &tree.path(0).unwrap()).unwrap(),
+            root
+        );
+
+        let tree = MerkleTree::new(leaves.clone(), padding, 5).unwrap();
+        assert_eq!(
+            root_from_path(leaves[2], 2, &tree.path(2).unwrap()).unwrap(),
+            tree.root()
+        );
+
+        assert!(MerkleTree::new(vec![padding; 5], padding, 2).is_err());
+        assert!(MerkleTree::new(vec![], padding, 0).is_err());
+        assert!(MerkleTree::new(vec![], padding, MAX_DEPTH + 1).is_err());
+    }
+}
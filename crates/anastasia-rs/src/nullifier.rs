@@ -0,0 +1,224 @@
+use std::{
+    collections::HashSet,
+    fs::{self, File, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    public_inputs::Nullifier,
+    utils::{field_to_hex, hex_to_field},
+};
+
+/// Nullifiers a verifier has accepted, so that a device can act only once per scope and epoch.
+pub trait NullifierStore {
+    /// Records `nullifier`, failing if it was recorded before.
+    fn insert(&mut self, nullifier: &Nullifier) -> Result<(), String>;
+
+    fn contains(&self, nullifier: &Nullifier) -> bool;
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryNullifierStore {
+    nullifiers: HashSet<Nullifier>,
+}
+
+impl InMemoryNullifierStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.nullifiers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nullifiers.is_empty()
+    }
+}
+
+impl NullifierStore for InMemoryNullifierStore {
+    fn insert(&mut self, nullifier: &Nullifier) -> Result<(), String> {
+        if !self.nullifiers.insert(*nullifier) {
+            return Err(format!(
+                "Nullifier {} was already used in epoch {}",
+                field_to_hex(&nullifier.nullifier),
+                nullifier.epoch
+            ));
+        }
+        Ok(())
+    }
+
+    fn contains(&self, nullifier: &Nullifier) -> bool {
+        self.nullifiers.contains(nullifier)
+    }
+}
+
+/// [`InMemoryNullifierStore`] persisted to a file with one `scope epoch nullifier` line per
+/// nullifier, appended as nullifiers are inserted. A nullifier counts as inserted once its line
+/// is synced to the file.
+#[derive(Debug)]
+pub struct FileNullifierStore {
+    path: PathBuf,
+    file: File,
+    /// Length of the complete lines of the file
+    len: u64,
+    store: InMemoryNullifierStore,
+}
+
+impl FileNullifierStore {
+    /// Opens the store at `path`, creating the file if it does not exist.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref().to_path_buf();
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(format!("Failed to read nullifier file {:?}: {}", path, e)),
+        };
+
+        // a crash while inserting may leave a torn last line, whose nullifier was never accepted
+        let len = contents.rfind('\n').map_or(0, |i| i + 1);
+        let mut store = InMemoryNullifierStore::new();
+        for (i, line) in contents[..len].lines().enumerate() {
+            let nullifier =
+                parse_line(line).map_err(|e| format!("{:?}, line {}: {}", path, i + 1, e))?;
+            store.insert(&nullifier)?;
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| format!("Failed to open nullifier file {:?}: {}", path, e))?;
+        let len = len as u64;
+        if len < contents.len() as u64 {
+            file.set_len(len)
+                .map_err(|e| format!("Failed to truncate nullifier file {:?}: {}", path, e))?;
+        }
+        Ok(FileNullifierStore {
+            path,
+            file,
+            len,
+            store,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.store.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.store.is_empty()
+    }
+}
+
+impl NullifierStore for FileNullifierStore {
+    fn insert(&mut self, nullifier: &Nullifier) -> Result<(), String> {
+        if self.store.contains(nullifier) {
+            // fails with the same error as the in-memory store
+            return self.store.insert(nullifier);
+        }
+        let line = format!(
+            "{} {} {}\n",
+            field_to_hex(&nullifier.scope),
+            nullifier.epoch,
+            field_to_hex(&nullifier.nullifier)
+        );
+        let written = self
+            .file
+            .write_all(line.as_bytes())
+            .and_then(|_| self.file.sync_data());
+        if let Err(e) = written {
+            // drop what was partially written, so that the next line starts on a line boundary
+            let _ = self.file.set_len(self.len);
+            return Err(format!(
+                "Failed to write nullifier file {:?}: {}",
+                self.path, e
+            ));
+        }
+        self.len += line.len() as u64;
+        self.store.insert(nullifier)
+    }
+
+    fn contains(&self, nullifier: &Nullifier) -> bool {
+        self.store.contains(nullifier)
+    }
+}
+
+fn parse_line(line: &str) -> Result<Nullifier, String> {
+    let fields: Vec<_> = line.split_whitespace().collect();
+    let [scope, epoch, nullifier] = fields[..] else {
+        return Err("expected `scope epoch nullifier`".to_string());
+    };
+    Ok(Nullifier {
+        scope: hex_to_field(scope)?,
+        epoch: epoch
+            .parse()
+            .map_err(|e| format!("Invalid epoch `{}`: {}", epoch, e))?,
+        nullifier: hex_to_field(nullifier)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bn254::Fr;
+
+    fn nullifier(scope: u64, epoch: u64) -> Nullifier {
+        Nullifier {
+            scope: Fr::from(scope),
+            epoch,
+            nullifier: hex_to_field(
+                "0d5cfbaf71d4d08bb3df03768418852e36eca2d719bde2ed9674814b951c8ed4",
+            )
+            .unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_in_memory_nullifier_store() {
+        let mut store = InMemoryNullifierStore::new();
+        assert!(!store.contains(&nullifier(1, 20250901)));
+        store.insert(&nullifier(1, 20250901)).unwrap();
+        assert!(store.contains(&nullifier(1, 20250901)));
+        assert!(store.insert(&nullifier(1, 20250901)).is_err());
+
+        // the same value in another scope or epoch is a different nullifier
+        store.insert(&nullifier(2, 20250901)).unwrap();
+        store.insert(&nullifier(1, 20250902)).unwrap();
+        assert_eq!(store.len(), 3);
+    }
+
+    #[test]
+    fn test_file_nullifier_store() {
+        let path =
+            std::env::temp_dir().join(format!("anastasia-nullifiers-{}.txt", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let mut store = FileNullifierStore::open(&path).unwrap();
+        assert!(store.is_empty());
+        store.insert(&nullifier(1, 20250901)).unwrap();
+        store.insert(&nullifier(2, 20250901)).unwrap();
+        assert!(store.insert(&nullifier(1, 20250901)).is_err());
+        drop(store);
+
+        let mut store = FileNullifierStore::open(&path).unwrap();
+        assert_eq!(store.len(), 2);
+        assert!(store.contains(&nullifier(2, 20250901)));
+        assert!(store.insert(&nullifier(1, 20250901)).is_err());
+        drop(store);
+
+        // a torn last line is dropped, and the store keeps working after it
+        let line = fs::read_to_string(&path).unwrap();
+        fs::write(&path, format!("{}{}", line, &line[..20])).unwrap();
+        let mut store = FileNullifierStore::open(&path).unwrap();
+        assert_eq!(store.len(), 2);
+        store.insert(&nullifier(3, 20250901)).unwrap();
+        drop(store);
+        assert_eq!(FileNullifierStore::open(&path).unwrap().len(), 3);
+
+        fs::write(&path, "0x01 not-an-epoch 0x02\n").unwrap();
+        assert!(FileNullifierStore::open(&path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -2,4 +2,4 @@ mod config;
 mod crh;
 
 pub use config::get_poseidon_parameters_2;
-pub use crh::CRH;
+pub use crh::{CRH, TwoToOneCRH};
@@ -0,0 +1,164 @@
+//! Threshold predicates over KeyDescription integers.
+//!
+//! The `es256_ee_attested` circuit proves `value >= threshold` for up to
+//! [`AttestationDisclosure::MAX_PREDICATES`](crate::AttestationDisclosure) predicates, disclosing
+//! the thresholds and results but not the values. A predicate that fails to hold still yields a
+//! valid proof, so verifiers must check the disclosed results.
+
+use crate::key_description::{
+    KM_TAG_BOOT_PATCHLEVEL, KM_TAG_CREATION_DATETIME, KM_TAG_OS_PATCHLEVEL, KM_TAG_OS_VERSION,
+    KM_TAG_VENDOR_PATCHLEVEL, KeyDescription,
+};
+
+/// AuthorizationList integers a predicate can compare. creationDateTime is read from
+/// softwareEnforced, the others from hardwareEnforced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PredicateAttribute {
+    /// Milliseconds since the Unix epoch
+    CreationDateTime,
+    /// e.g. 160000 for Android 16
+    OsVersion,
+    /// YYYYMM
+    OsPatchLevel,
+    /// YYYYMMDD
+    VendorPatchLevel,
+    /// YYYYMMDD
+    BootPatchLevel,
+}
+
+impl PredicateAttribute {
+    pub fn tag(self) -> u32 {
+        match self {
+            PredicateAttribute::CreationDateTime => KM_TAG_CREATION_DATETIME,
+            PredicateAttribute::OsVersion => KM_TAG_OS_VERSION,
+            PredicateAttribute::OsPatchLevel => KM_TAG_OS_PATCHLEVEL,
+            PredicateAttribute::VendorPatchLevel => KM_TAG_VENDOR_PATCHLEVEL,
+            PredicateAttribute::BootPatchLevel => KM_TAG_BOOT_PATCHLEVEL,
+        }
+    }
+
+    pub fn from_tag(tag: u32) -> Result<Self, String> {
+        match tag {
+            KM_TAG_CREATION_DATETIME => Ok(PredicateAttribute::CreationDateTime),
+            KM_TAG_OS_VERSION => Ok(PredicateAttribute::OsVersion),
+            KM_TAG_OS_PATCHLEVEL => Ok(PredicateAttribute::OsPatchLevel),
+            KM_TAG_VENDOR_PATCHLEVEL => Ok(PredicateAttribute::VendorPatchLevel),
+            KM_TAG_BOOT_PATCHLEVEL => Ok(PredicateAttribute::BootPatchLevel),
+            _ => Err(format!("Unsupported predicate tag {}", tag)),
+        }
+    }
+
+    fn value(self, key_description: &KeyDescription) -> Option<u64> {
+        let sw = &key_description.software_enforced;
+        let hw = &key_description.hardware_enforced;
+        let located = match self {
+            PredicateAttribute::CreationDateTime => &sw.creation_date_time,
+            PredicateAttribute::OsVersion => &hw.os_version,
+            PredicateAttribute::OsPatchLevel => &hw.os_patch_level,
+            PredicateAttribute::VendorPatchLevel => &hw.vendor_patch_level,
+            PredicateAttribute::BootPatchLevel => &hw.boot_patch_level,
+        };
+        located.as_ref().map(|located| located.value)
+    }
+}
+
+/// `attribute >= threshold`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Predicate {
+    pub attribute: PredicateAttribute,
+    pub threshold: u64,
+}
+
+impl Predicate {
+    pub fn new(attribute: PredicateAttribute, threshold: u64) -> Self {
+        Predicate {
+            attribute,
+            threshold,
+        }
+    }
+
+    pub fn evaluate(&self, key_description: &KeyDescription) -> Result<bool, String> {
+        self.attribute
+            .value(key_description)
+            .map(|value| value >= self.threshold)
+            .ok_or_else(|| format!("Key attestation has no {:?}", self.attribute))
+    }
+}
+
+/// A predicate together with the result proved by the circuit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PredicateResult {
+    pub predicate: Predicate,
+    pub holds: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cert::ParsedCert;
+
+    #[test]
+    fn test_evaluate() {
+        let cert = include_bytes!("../test_data/es256_ee.der");
+        let kd = ParsedCert::from_der(cert)
+            .unwrap()
+            .key_description()
+            .unwrap()
+            .unwrap();
+
+        let os_patch_level = PredicateAttribute::OsPatchLevel;
+        assert!(
+            Predicate::new(os_patch_level, 202405)
+                .evaluate(&kd)
+                .unwrap()
+        );
+        assert!(
+            Predicate::new(os_patch_level, 202508)
+                .evaluate(&kd)
+                .unwrap()
+        );
+        assert!(
+            !Predicate::new(os_patch_level, 202509)
+                .evaluate(&kd)
+                .unwrap()
+        );
+
+        let created = PredicateAttribute::CreationDateTime;
+        assert!(
+            Predicate::new(created, 0x019903ec61b9)
+                .evaluate(&kd)
+                .unwrap()
+        );
+        assert!(
+            !Predicate::new(created, 0x019903ec61ba)
+                .evaluate(&kd)
+                .unwrap()
+        );
+
+        let mut kd = kd;
+        kd.hardware_enforced.boot_patch_level = None;
+        assert!(
+            Predicate::new(PredicateAttribute::BootPatchLevel, 0)
+                .evaluate(&kd)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_tag() {
+        for attribute in [
+            PredicateAttribute::CreationDateTime,
+            PredicateAttribute::OsVersion,
+            PredicateAttribute::OsPatchLevel,
+            PredicateAttribute::VendorPatchLevel,
+            PredicateAttribute::BootPatchLevel,
+        ] {
+            assert_eq!(
+                PredicateAttribute::from_tag(attribute.tag()).unwrap(),
+                attribute
+            );
+        }
+        assert!(PredicateAttribute::from_tag(3).is_err()); // keySize
+        assert!(PredicateAttribute::from_tag(0).is_err());
+    }
+}
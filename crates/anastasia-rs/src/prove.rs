@@ -1,13 +1,22 @@
 use crate::{
-    cert::ParsedCert,
+    anchors::AnchorSet,
+    api::{AttestationRequest, IssuerInputs},
+    cert::{ParsedCert, extract_ecdsa_der},
     circuit::Circuit,
-    utils::{UtcTime, commit_attrs, field_to_hex, from_u8_array_to_fr_vec, hex_to_field},
+    key_description::KeyDescription,
+    predicate::PredicateResult,
+    public_inputs::{AttestationDisclosure, NO_PATH_LEN, Possession},
+    revocation::RevocationTree,
+    utils::{
+        self, TimeGranularity, UtcTime, app_id_hash, commit_attrs, field_to_hex,
+        from_u8_array_to_fr_vec, hex_to_field,
+    },
 };
 
 use ark_bn254::Fr;
-use ark_ff::UniformRand;
+use ark_ff::{AdditiveGroup, UniformRand};
 use ark_std::rand::rngs::OsRng;
-use chrono::{DateTime, Datelike, Timelike, Utc};
+use chrono::{DateTime, Utc};
 use noir::{
     FieldElement,
     acir_field::GenericFieldElement,
@@ -15,21 +24,48 @@ use noir::{
     native_types::{Witness, WitnessMap},
 };
 
+/// Optional sections of the witness. Each one must be given exactly when the public inputs
+/// layout of the circuit has it.
+#[derive(Clone, Copy, Default)]
+pub struct WitnessOptions<'a> {
+    /// The current time if not set
+    pub now: Option<DateTime<Utc>>,
+    pub now_granularity: TimeGranularity,
+    pub attestation: Option<&'a AttestationRequest>,
+    /// (ctx, user_secret)
+    pub nym: Option<(Fr, Fr)>,
+    /// (scope, epoch, secret)
+    pub nullifier: Option<(Fr, u64, Fr)>,
+    /// (challenge, DER signature of the challenge by the subject key)
+    pub possession: Option<(&'a Possession, &'a [u8])>,
+    pub revocation: Option<&'a RevocationTree>,
+    pub anchor_set: Option<&'a AnchorSet>,
+}
+
 pub fn prove(
     circuit: &Circuit,
     cert: Vec<u8>,
-    now: Option<DateTime<Utc>>,
-    authority_key_id: Vec<u8>,
-    issuer_pk_x: Vec<u8>,
-    issuer_pk_y: Vec<u8>,
-    prev_cmt: String,
-    prev_cmt_r: String,
-    max_extra_extension_len: usize,
+    issuer: &IssuerInputs,
+    options: WitnessOptions,
 ) -> Result<(Vec<u8>, String, String), String> {
-    println!(
-        "Debug: max_extra_extension_len = {}",
-        max_extra_extension_len
-    );
+    if let (Some(tree), Some(depth)) = (options.revocation, circuit.revocation_tree_depth)
+        && tree.depth() != depth
+    {
+        return Err(format!(
+            "Revocation tree has depth {}, but the circuit expects {}",
+            tree.depth(),
+            depth
+        ));
+    }
+    if let (Some(anchor_set), Some(depth)) = (options.anchor_set, circuit.anchor_set_depth)
+        && anchor_set.depth() != depth
+    {
+        return Err(format!(
+            "Anchor set has depth {}, but the circuit expects {}",
+            anchor_set.depth(),
+            depth
+        ));
+    }
 
     let parsed_cert =
         ParsedCert::from_der(&cert).map_err(|e| format!("Failed to parse cert: {}", e))?;
@@ -44,24 +80,8 @@ pub fn prove(
         next_cmt_r,
     )?;
 
-    let initial_witness = generate_witness(
-        parsed_cert,
-        now,
-        authority_key_id
-            .try_into()
-            .map_err(|_| "authority_key_id must be 20 bytes")?,
-        issuer_pk_x
-            .try_into()
-            .map_err(|_| "issuer_pk_x must be 32 bytes")?,
-        issuer_pk_y
-            .try_into()
-            .map_err(|_| "issuer_pk_y must be 32 bytes")?,
-        hex_to_field(&prev_cmt)?,
-        hex_to_field(&prev_cmt_r)?,
-        next_cmt,
-        next_cmt_r,
-        max_extra_extension_len,
-    )?;
+    let initial_witness =
+        generate_witness(parsed_cert, issuer, next_cmt, next_cmt_r, circuit, options)?;
 
     let proof_with_public_inputs = prove_ultra_honk_keccak(
         &circuit.bytecode,
@@ -70,9 +90,8 @@ pub fn prove(
         false,
         false,
     )?;
-    //let (proof, _) = split_honk_proof(&proof_with_public_inputs, circuit.public_input_size)
-    //    .ok_or("Failed to split honk proof")?;
-    let proof = proof_with_public_inputs; // TODO: remove public inputs from proof
+    // Public inputs are kept in front of the proof; use `split_proof` to separate them
+    let proof = proof_with_public_inputs;
 
     // let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
     // encoder
@@ -93,27 +112,52 @@ pub fn prove(
 
 pub fn generate_witness(
     parsed_cert: ParsedCert,
-    now: Option<DateTime<Utc>>,
-    authority_key_id: [u8; 20],
-    issuer_pk_x: [u8; 32],
-    issuer_pk_y: [u8; 32],
-    prev_cmt: Fr,
-    prev_cmt_r: Fr,
+    issuer: &IssuerInputs,
     next_cmt: Fr,
     next_cmt_r: Fr,
-    max_extra_extension_len: usize,
+    circuit: &Circuit,
+    options: WitnessOptions,
 ) -> Result<WitnessMap<GenericFieldElement<Fr>>, String> {
+    let Circuit {
+        max_extra_extension_len,
+        max_extra_extension_count,
+        public_inputs_layout,
+        ..
+    } = *circuit;
+    let WitnessOptions {
+        now,
+        now_granularity,
+        attestation,
+        nym,
+        nullifier,
+        possession,
+        revocation,
+        anchor_set,
+    } = options;
+    let authority_key_id: [u8; 20] = issuer
+        .authority_key_id
+        .clone()
+        .try_into()
+        .map_err(|_| "authority_key_id must be 20 bytes")?;
+    let issuer_pk_x: [u8; 32] = issuer
+        .pk_x
+        .clone()
+        .try_into()
+        .map_err(|_| "issuer_pk_x must be 32 bytes")?;
+    let issuer_pk_y: [u8; 32] = issuer
+        .pk_y
+        .clone()
+        .try_into()
+        .map_err(|_| "issuer_pk_y must be 32 bytes")?;
     let mut witness: Vec<Fr> = Vec::new();
 
     let datetime = now.unwrap_or_else(|| Utc::now());
-    let now = UtcTime {
-        year: datetime.year() as u16,
-        month: datetime.month() as u8,
-        day: datetime.day() as u8,
-        hour: datetime.hour() as u8,
-        minute: datetime.minute() as u8,
-        second: datetime.second() as u8,
+    let datetime = match (public_inputs_layout.now_granularity, now_granularity) {
+        (_, TimeGranularity::Second) => datetime,
+        (true, granularity) => coarse_now(&parsed_cert, &datetime, granularity)?,
+        (false, _) => return Err("Circuit does not support a coarse now".to_string()),
     };
+    let now = UtcTime::from_datetime(&datetime);
 
     witness.extend(from_u8_array_to_fr_vec(&issuer_pk_x));
     witness.extend(from_u8_array_to_fr_vec(&issuer_pk_y));
@@ -133,24 +177,193 @@ pub fn generate_witness(
     witness.extend(from_u8_array_to_fr_vec(&authority_key_id));
     witness.push(parsed_cert.subject_key_identifier_index.into());
     witness.push(parsed_cert.authority_key_identifier_index.into());
-    witness.push(parsed_cert.basic_constraints_ca_index.into());
-    witness.push(parsed_cert.key_usage_key_cert_sign_index.into());
-    witness.push(parsed_cert.key_usage_digital_signature_index.into());
+    witness.push(parsed_cert.basic_constraints_index.into());
+    witness.push((parsed_cert.basic_constraints_critical as u32).into());
+    witness.push((parsed_cert.basic_constraints_ca as u32).into());
+    witness.push((parsed_cert.basic_constraints_path_len.is_some() as u32).into());
+    witness.push(parsed_cert.basic_constraints_path_len.unwrap_or(0).into());
+    witness.push(parsed_cert.key_usage_index.into());
+    witness.push((parsed_cert.key_usage_critical as u32).into());
+    witness.push((parsed_cert.key_usage as u32).into());
+
+    if parsed_cert.extra_extension.len() > max_extra_extension_len {
+        return Err(format!(
+            "Extra extensions are {} bytes, exceeding the circuit maximum of {}",
+            parsed_cert.extra_extension.len(),
+            max_extra_extension_len
+        ));
+    }
+    if parsed_cert.extra_extension_offsets.len() > max_extra_extension_count {
+        return Err(format!(
+            "Certificate has {} extra extensions, exceeding the circuit maximum of {}",
+            parsed_cert.extra_extension_offsets.len(),
+            max_extra_extension_count
+        ));
+    }
 
     let mut extra_extension_array = vec![0u8; max_extra_extension_len];
-    let copy_len = std::cmp::min(parsed_cert.extra_extension.len(), max_extra_extension_len);
-    extra_extension_array[..copy_len].copy_from_slice(&parsed_cert.extra_extension[..copy_len]);
+    extra_extension_array[..parsed_cert.extra_extension.len()]
+        .copy_from_slice(&parsed_cert.extra_extension);
+    let mut extra_extension_offsets = vec![0u32; max_extra_extension_count];
+    extra_extension_offsets[..parsed_cert.extra_extension_offsets.len()]
+        .copy_from_slice(&parsed_cert.extra_extension_offsets);
 
     witness.extend(from_u8_array_to_fr_vec(&extra_extension_array));
     witness.push(parsed_cert.extra_extension_len.into());
+    witness.extend(
+        extra_extension_offsets
+            .iter()
+            .map(|offset| Fr::from(*offset)),
+    );
+    witness.push((parsed_cert.extra_extension_offsets.len() as u32).into());
     witness.extend(from_u8_array_to_fr_vec(&parsed_cert.not_before));
     witness.extend(from_u8_array_to_fr_vec(&parsed_cert.not_after));
+    witness.push((parsed_cert.not_before_generalized as u32).into());
+    witness.push((parsed_cert.not_after_generalized as u32).into());
     witness.extend(from_u8_array_to_fr_vec(&now.to_bytes()));
-    witness.push(prev_cmt);
-    witness.push(prev_cmt_r);
+    if public_inputs_layout.now_granularity {
+        witness.push((now_granularity as u32).into());
+    }
+    match (public_inputs_layout.anchor_set, anchor_set) {
+        (true, Some(anchor_set)) => {
+            // the anchor commitment with the randomness 0 stands in for `prev_cmt`
+            let aki = if parsed_cert.authority_key_identifier_index == 0 {
+                authority_key_id
+            } else {
+                parsed_cert.authority_key_identifier
+            };
+            let anchor_cmt =
+                commit_attrs(parsed_cert.issuer, aki, issuer_pk_x, issuer_pk_y, Fr::ZERO)?;
+            let (index, path) = anchor_set.membership_witness(anchor_cmt)?;
+            witness.push(anchor_set.root());
+            witness.push(anchor_cmt);
+            witness.push(index.into());
+            witness.extend(path);
+        }
+        (true, None) => {
+            return Err(
+                "Circuit proves anchor-set membership, but no anchor set is given".to_string(),
+            );
+        }
+        (false, Some(_)) => {
+            return Err("Circuit does not prove anchor-set membership".to_string());
+        }
+        (false, None) => {
+            witness.push(hex_to_field(&issuer.cmt)?);
+            witness.push(hex_to_field(&issuer.cmt_r)?);
+        }
+    }
     witness.push(next_cmt);
     witness.push(next_cmt_r);
 
+    if public_inputs_layout.path_len {
+        if !parsed_cert.may_sign_certificates() {
+            return Err(
+                "Circuit proves a CA certificate, but the certificate may not sign certificates"
+                    .to_string(),
+            );
+        }
+        witness.push(
+            parsed_cert
+                .basic_constraints_path_len
+                .unwrap_or(NO_PATH_LEN.into())
+                .into(),
+        );
+    }
+
+    match (public_inputs_layout.revocation, revocation) {
+        (true, Some(tree)) => {
+            let serial_number =
+                &parsed_cert.serial_number[..parsed_cert.serial_number_len as usize];
+            let non_revocation = tree.non_revocation_witness(serial_number)?;
+            witness.push(tree.root());
+            witness.push(non_revocation.low);
+            witness.push(non_revocation.high);
+            witness.push(non_revocation.index.into());
+            witness.extend(non_revocation.path);
+        }
+        (true, None) => {
+            return Err(
+                "Circuit proves non-revocation, but no revocation tree is given".to_string(),
+            );
+        }
+        (false, Some(_)) => return Err("Circuit does not prove non-revocation".to_string()),
+        (false, None) => {}
+    }
+
+    match (public_inputs_layout.attestation, attestation) {
+        (true, Some(request)) => {
+            witness.extend(generate_attestation_witness(&parsed_cert, request)?);
+        }
+        (true, None) => {
+            return Err(
+                "Circuit discloses attestation properties, but no attestation request is given"
+                    .to_string(),
+            );
+        }
+        (false, Some(_)) => {
+            return Err("Circuit does not disclose attestation properties".to_string());
+        }
+        (false, None) => {}
+    }
+
+    match (public_inputs_layout.nym, nym) {
+        (true, Some((ctx, user_secret))) => {
+            witness.push(user_secret);
+            witness.push(ctx);
+            witness.push(utils::nym(
+                parsed_cert.subject_pk_x,
+                parsed_cert.subject_pk_y,
+                ctx,
+                user_secret,
+            )?);
+        }
+        (true, None) => {
+            return Err("Circuit computes a nym, but no ctx and user secret are given".to_string());
+        }
+        (false, Some(_)) => return Err("Circuit does not compute a nym".to_string()),
+        (false, None) => {}
+    }
+
+    match (public_inputs_layout.nullifier, nullifier) {
+        (true, Some((scope, epoch, secret))) => {
+            witness.push(secret);
+            witness.push(scope);
+            witness.push(epoch.into());
+            witness.push(utils::nullifier(
+                parsed_cert.subject_pk_x,
+                parsed_cert.subject_pk_y,
+                scope,
+                epoch,
+                secret,
+            )?);
+        }
+        (true, None) => {
+            return Err(
+                "Circuit computes a nullifier, but no scope, epoch and secret are given"
+                    .to_string(),
+            );
+        }
+        (false, Some(_)) => return Err("Circuit does not compute a nullifier".to_string()),
+        (false, None) => {}
+    }
+
+    match (public_inputs_layout.possession, possession) {
+        (true, Some((possession, signature))) => {
+            let signature = extract_ecdsa_der(signature)
+                .map_err(|e| format!("Failed to parse possession signature: {}", e))?;
+            witness.extend(from_u8_array_to_fr_vec(&signature));
+            witness.extend(from_u8_array_to_fr_vec(&possession.challenge));
+        }
+        (true, None) => {
+            return Err(
+                "Circuit proves possession, but no challenge and signature are given".to_string(),
+            );
+        }
+        (false, Some(_)) => return Err("Circuit does not prove possession".to_string()),
+        (false, None) => {}
+    }
+
     let mut witness_map = WitnessMap::new();
     for (i, witness) in witness.iter().enumerate() {
         witness_map.insert(Witness(i as u32), FieldElement::from_repr(*witness));
@@ -158,3 +371,628 @@ pub fn generate_witness(
 
     Ok(witness_map)
 }
+
+/// Truncates `now` to `granularity`, requiring the whole period to lie within the validity of
+/// the certificate as the circuit does.
+fn coarse_now(
+    parsed_cert: &ParsedCert,
+    now: &DateTime<Utc>,
+    granularity: TimeGranularity,
+) -> Result<DateTime<Utc>, String> {
+    let start = granularity.truncate(now);
+    let not_before = UtcTime::from_bytes(parsed_cert.not_before).to_datetime()?;
+    let not_after = UtcTime::from_bytes(parsed_cert.not_after).to_datetime()?;
+    if not_before >= start || granularity.period_end(&start) >= not_after {
+        return Err(format!(
+            "The {:?} containing now is not within the validity period of the certificate",
+            granularity
+        ));
+    }
+    Ok(start)
+}
+
+fn generate_attestation_witness(
+    parsed_cert: &ParsedCert,
+    request: &AttestationRequest,
+) -> Result<Vec<Fr>, String> {
+    let attestation = attestation_witness(parsed_cert, request)?;
+    let nonce = &request.nonce;
+    if attestation.disclosure.challenge != *nonce {
+        return Err("Attestation challenge does not match the nonce".to_string());
+    }
+
+    let mut witness: Vec<Fr> = vec![
+        attestation.key_description_offset.into(),
+        attestation.security_level_offset.into(),
+        attestation.device_locked_offset.into(),
+        attestation.verified_boot_state_offset.into(),
+        attestation.app_id_package_info_offset.into(),
+        attestation.app_id_signature_digest_offset.into(),
+        (attestation.disclosure.security_level as u32).into(),
+        (attestation.disclosure.device_locked as u32).into(),
+        (attestation.disclosure.verified_boot_state as u32).into(),
+    ];
+    let mut challenge = [0u8; AttestationDisclosure::MAX_CHALLENGE_LEN];
+    challenge[..nonce.len()].copy_from_slice(nonce);
+    witness.extend(from_u8_array_to_fr_vec(&challenge));
+    witness.push((nonce.len() as u32).into());
+
+    let slots = || {
+        (0..AttestationDisclosure::MAX_PREDICATES).map(|i| attestation.disclosure.predicates.get(i))
+    };
+    witness.extend(slots().map(|r| Fr::from(r.map_or(0, |r| r.predicate.attribute.tag()))));
+    witness.extend(slots().map(|r| Fr::from(r.map_or(0, |r| r.predicate.threshold))));
+    witness.extend(slots().map(|r| Fr::from(r.is_some_and(|r| r.holds) as u32)));
+
+    witness.push(attestation.disclosure.app_id_hash.unwrap_or(Fr::ZERO));
+    witness.extend((0..AttestationDisclosure::MAX_ALLOWED_APPS).map(|i| {
+        attestation
+            .disclosure
+            .app_id_allow_list
+            .get(i)
+            .copied()
+            .unwrap_or(Fr::ZERO)
+    }));
+    Ok(witness)
+}
+
+/// Offsets within `extra_extension` from which the attested EE circuit re-derives the
+/// disclosed attestation properties.
+struct AttestationWitness {
+    key_description_offset: u32,
+    security_level_offset: u32,
+    device_locked_offset: u32,
+    verified_boot_state_offset: u32,
+    /// 0 unless the app id is proved
+    app_id_package_info_offset: u32,
+    app_id_signature_digest_offset: u32,
+    disclosure: AttestationDisclosure,
+}
+
+fn attestation_witness(
+    parsed_cert: &ParsedCert,
+    request: &AttestationRequest,
+) -> Result<AttestationWitness, String> {
+    let predicates = &request.predicates;
+    let key_description_offset = parsed_cert
+        .key_description_offset()
+        .ok_or("Certificate has no key attestation extension")?;
+    let key_description = KeyDescription::from_extension(
+        &parsed_cert.extra_extension[key_description_offset as usize..],
+    )?;
+    if predicates.len() > AttestationDisclosure::MAX_PREDICATES {
+        return Err(format!(
+            "{} predicates exceed the maximum of {}",
+            predicates.len(),
+            AttestationDisclosure::MAX_PREDICATES
+        ));
+    }
+    let predicates = predicates
+        .iter()
+        .map(|predicate| {
+            Ok(PredicateResult {
+                predicate: *predicate,
+                holds: predicate.evaluate(&key_description)?,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let app_id_allow_list = request.app_id_allow_list()?;
+    let (app_id_package_info_offset, app_id_signature_digest_offset, app_id) =
+        if request.disclose_app_id || !app_id_allow_list.is_empty() {
+            let (package_info_offset, signature_digest_offset, app_id) =
+                select_app_id(&key_description, &app_id_allow_list)?;
+            (
+                key_description_offset + package_info_offset,
+                key_description_offset + signature_digest_offset,
+                Some(app_id),
+            )
+        } else {
+            (0, 0, None)
+        };
+
+    let root_of_trust = key_description
+        .hardware_enforced
+        .root_of_trust
+        .ok_or("Key attestation has no hardware-enforced rootOfTrust")?;
+    let challenge = key_description.attestation_challenge.value;
+    if challenge.len() > AttestationDisclosure::MAX_CHALLENGE_LEN {
+        return Err(format!(
+            "Attestation challenge is {} bytes, exceeding the maximum of {}",
+            challenge.len(),
+            AttestationDisclosure::MAX_CHALLENGE_LEN
+        ));
+    }
+
+    Ok(AttestationWitness {
+        key_description_offset,
+        security_level_offset: key_description_offset
+            + key_description.attestation_security_level.offset,
+        device_locked_offset: key_description_offset + root_of_trust.device_locked.offset,
+        verified_boot_state_offset: key_description_offset
+            + root_of_trust.verified_boot_state.offset,
+        app_id_package_info_offset,
+        app_id_signature_digest_offset,
+        disclosure: AttestationDisclosure {
+            security_level: key_description.attestation_security_level.value,
+            device_locked: root_of_trust.device_locked.value,
+            verified_boot_state: root_of_trust.verified_boot_state.value,
+            challenge,
+            predicates,
+            app_id_hash: app_id.filter(|_| request.disclose_app_id),
+            app_id_allow_list,
+        },
+    })
+}
+
+// An attestationApplicationId lists every package sharing the app's UID and every signing
+// certificate digest; pick a combination in `allow_list`, or the first one if it is empty.
+fn select_app_id(
+    key_description: &KeyDescription,
+    allow_list: &[Fr],
+) -> Result<(u32, u32, Fr), String> {
+    let app_id = key_description
+        .software_enforced
+        .attestation_application_id
+        .as_ref()
+        .ok_or("Key attestation has no attestationApplicationId")?;
+    for package_info in &app_id.package_infos {
+        for digest in &app_id.signature_digests {
+            let hash = app_id_hash(&package_info.package_name.value, &digest.value)?;
+            if allow_list.is_empty() || allow_list.contains(&hash) {
+                return Ok((package_info.offset, digest.offset, hash));
+            }
+        }
+    }
+    Err("Attesting app is not in the app id allow-list".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        anchors::TrustAnchorStore,
+        api::CommitResult,
+        cert::SubjectAttrs,
+        key_description::{SecurityLevel, VerifiedBootState},
+        predicate::{Predicate, PredicateAttribute},
+        public_inputs::PublicInputsLayout,
+        revocation::RevocationList,
+        utils::hex_to_field,
+    };
+
+    const APP_ID: &str = "082fa0ba5ca89c1918e3cedccde7bb5968fab935d664c3e8ba68b267b995de74";
+
+    fn request(predicates: &[Predicate]) -> AttestationRequest {
+        AttestationRequest {
+            nonce: vec![0x00],
+            predicates: predicates.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_attestation_witness() {
+        let cert = include_bytes!("../test_data/es256_ee.der");
+        let parsed_cert = ParsedCert::from_der(cert).unwrap();
+        let predicates = [
+            Predicate::new(PredicateAttribute::OsPatchLevel, 202405),
+            Predicate::new(PredicateAttribute::VendorPatchLevel, 20250806),
+        ];
+        let attestation = attestation_witness(&parsed_cert, &request(&predicates)).unwrap();
+        assert_eq!(attestation.key_description_offset, 0);
+        assert_eq!(attestation.security_level_offset, 28);
+        assert_eq!(attestation.device_locked_offset, 220);
+        assert_eq!(attestation.verified_boot_state_offset, 223);
+        assert_eq!(attestation.app_id_package_info_offset, 0);
+        assert_eq!(attestation.app_id_signature_digest_offset, 0);
+        assert_eq!(
+            attestation.disclosure,
+            AttestationDisclosure {
+                security_level: SecurityLevel::StrongBox,
+                device_locked: true,
+                verified_boot_state: VerifiedBootState::Verified,
+                challenge: vec![0x00],
+                predicates: vec![
+                    PredicateResult {
+                        predicate: predicates[0],
+                        holds: true,
+                    },
+                    PredicateResult {
+                        predicate: predicates[1],
+                        holds: false,
+                    },
+                ],
+                app_id_hash: None,
+                app_id_allow_list: Vec::new(),
+            }
+        );
+        assert!(attestation_witness(&parsed_cert, &request(&[predicates[0]; 5])).is_err());
+
+        let cert = include_bytes!("../test_data/es256_ca.der");
+        let parsed_cert = ParsedCert::from_der(cert).unwrap();
+        assert!(attestation_witness(&parsed_cert, &request(&[])).is_err());
+    }
+
+    #[test]
+    fn test_attestation_witness_with_app_id() {
+        let cert = include_bytes!("../test_data/es256_ee.der");
+        let parsed_cert = ParsedCert::from_der(cert).unwrap();
+        let app_id = hex_to_field(APP_ID).unwrap();
+
+        let disclosed = AttestationRequest {
+            disclose_app_id: true,
+            ..request(&[])
+        };
+        let attestation = attestation_witness(&parsed_cert, &disclosed).unwrap();
+        assert_eq!(attestation.app_id_package_info_offset, 67);
+        assert_eq!(attestation.app_id_signature_digest_offset, 96);
+        assert_eq!(attestation.disclosure.app_id_hash, Some(app_id));
+        assert!(attestation.disclosure.app_id_allow_list.is_empty());
+
+        let allowed = AttestationRequest {
+            app_id_allow_list: vec!["01".to_string(), APP_ID.to_string()],
+            ..request(&[])
+        };
+        let attestation = attestation_witness(&parsed_cert, &allowed).unwrap();
+        assert_eq!(attestation.app_id_package_info_offset, 67);
+        assert_eq!(attestation.disclosure.app_id_hash, None);
+        assert_eq!(
+            attestation.disclosure.app_id_allow_list,
+            vec![Fr::from(1u32), app_id]
+        );
+
+        let not_allowed = AttestationRequest {
+            app_id_allow_list: vec!["01".to_string()],
+            ..request(&[])
+        };
+        assert!(attestation_witness(&parsed_cert, &not_allowed).is_err());
+        let too_many = AttestationRequest {
+            app_id_allow_list: vec![
+                APP_ID.to_string();
+                AttestationDisclosure::MAX_ALLOWED_APPS + 1
+            ],
+            ..request(&[])
+        };
+        assert!(attestation_witness(&parsed_cert, &too_many).is_err());
+    }
+
+    const EE_CERT: &[u8] = include_bytes!("../test_data/es256_ee.der");
+    const CA_CERT: &[u8] = include_bytes!("../test_data/es256_ca.der");
+
+    /// Witness values of `cert` issued by es256_ca, in a circuit with the limits of es256_ee
+    fn witness(
+        cert: &[u8],
+        layout: PublicInputsLayout,
+        options: WitnessOptions,
+    ) -> Result<Vec<GenericFieldElement<Fr>>, String> {
+        let circuit = Circuit {
+            id: "test".to_string(),
+            noir_version: None,
+            bytecode: String::new(),
+            verification_key: Vec::new(),
+            circuit_size: 0,
+            public_input_size: None,
+            max_extra_extension_len: 300,
+            max_extra_extension_count: 4,
+            anchor_set_depth: None,
+            revocation_tree_depth: None,
+            public_inputs_layout: layout,
+        };
+        let issuer = IssuerInputs::new(
+            &SubjectAttrs::from_der(CA_CERT).unwrap(),
+            CommitResult {
+                cmt: "01".to_string(),
+                r: "02".to_string(),
+            },
+        );
+        let witness = generate_witness(
+            ParsedCert::from_der(cert).unwrap(),
+            &issuer,
+            Fr::from(3u32),
+            Fr::from(4u32),
+            &circuit,
+            options,
+        )?;
+        Ok(witness.into_iter().map(|(_, value)| value).collect())
+    }
+
+    fn plain_len(cert: &[u8]) -> usize {
+        witness(
+            cert,
+            PublicInputsLayout::default(),
+            WitnessOptions::default(),
+        )
+        .unwrap()
+        .len()
+    }
+
+    fn now(rfc3339: &str) -> Option<DateTime<Utc>> {
+        Some(
+            DateTime::parse_from_rfc3339(rfc3339)
+                .unwrap()
+                .with_timezone(&Utc),
+        )
+    }
+
+    fn field(hex: &str) -> GenericFieldElement<Fr> {
+        FieldElement::from_repr(hex_to_field(hex).unwrap())
+    }
+
+    #[test]
+    fn test_generate_witness_with_attestation() {
+        let layout = PublicInputsLayout {
+            attestation: true,
+            ..Default::default()
+        };
+        let request = request(&[]);
+        let options = WitnessOptions {
+            attestation: Some(&request),
+            ..Default::default()
+        };
+        assert_eq!(
+            witness(EE_CERT, layout, options).unwrap().len(),
+            plain_len(EE_CERT)
+                + 9
+                + AttestationDisclosure::MAX_CHALLENGE_LEN
+                + 1
+                + 3 * AttestationDisclosure::MAX_PREDICATES
+                + 1
+                + AttestationDisclosure::MAX_ALLOWED_APPS
+        );
+        assert!(witness(EE_CERT, layout, WitnessOptions::default()).is_err());
+        assert!(witness(EE_CERT, PublicInputsLayout::default(), options).is_err());
+    }
+
+    #[test]
+    fn test_generate_witness_checks_nonce() {
+        let layout = PublicInputsLayout {
+            attestation: true,
+            ..Default::default()
+        };
+        for nonce in [vec![0x01], vec![]] {
+            let request = AttestationRequest {
+                nonce,
+                ..Default::default()
+            };
+            let options = WitnessOptions {
+                attestation: Some(&request),
+                ..Default::default()
+            };
+            assert!(witness(EE_CERT, layout, options).is_err());
+        }
+    }
+
+    #[test]
+    fn test_generate_witness_checks_predicates() {
+        let layout = PublicInputsLayout {
+            attestation: true,
+            ..Default::default()
+        };
+        let request = request(&[Predicate::new(PredicateAttribute::OsVersion, 150000)]);
+        let options = WitnessOptions {
+            attestation: Some(&request),
+            ..Default::default()
+        };
+        assert!(witness(EE_CERT, layout, options).is_ok());
+        assert!(witness(EE_CERT, PublicInputsLayout::default(), options).is_err());
+    }
+
+    #[test]
+    fn test_generate_witness_with_nym() {
+        let layout = PublicInputsLayout {
+            nym: true,
+            ..Default::default()
+        };
+        let options = WitnessOptions {
+            nym: Some((Fr::from(1u32), Fr::from(0xcafeu32))),
+            ..Default::default()
+        };
+
+        let plain = plain_len(EE_CERT);
+        let values = witness(EE_CERT, layout, options).unwrap();
+        assert_eq!(values.len(), plain + 3);
+        assert_eq!(
+            values[plain + 2],
+            field("169cf2496642b471ab008314ab38b600845185c0527591850a73ab158cac17fe")
+        );
+
+        assert!(witness(EE_CERT, layout, WitnessOptions::default()).is_err());
+        assert!(witness(EE_CERT, PublicInputsLayout::default(), options).is_err());
+    }
+
+    #[test]
+    fn test_generate_witness_with_nullifier() {
+        let layout = PublicInputsLayout {
+            nullifier: true,
+            ..Default::default()
+        };
+        let options = WitnessOptions {
+            nullifier: Some((Fr::from(1u32), 20250901, Fr::from(0xcafeu32))),
+            ..Default::default()
+        };
+
+        let plain = plain_len(EE_CERT);
+        let values = witness(EE_CERT, layout, options).unwrap();
+        assert_eq!(values.len(), plain + 4);
+        assert_eq!(values[plain], FieldElement::from_repr(Fr::from(0xcafeu32)));
+        assert_eq!(
+            values[plain + 3],
+            field("0d5cfbaf71d4d08bb3df03768418852e36eca2d719bde2ed9674814b951c8ed4")
+        );
+
+        assert!(witness(EE_CERT, layout, WitnessOptions::default()).is_err());
+        assert!(witness(EE_CERT, PublicInputsLayout::default(), options).is_err());
+    }
+
+    #[test]
+    fn test_generate_witness_with_possession() {
+        let layout = PublicInputsLayout {
+            possession: true,
+            ..Default::default()
+        };
+        let possession = Possession {
+            challenge: [0x01; Possession::CHALLENGE_LEN],
+        };
+        let signature = hex::decode("300702020100020101").unwrap();
+        let options = WitnessOptions {
+            possession: Some((&possession, &signature)),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            witness(EE_CERT, layout, options).unwrap().len(),
+            plain_len(EE_CERT) + 64 + Possession::CHALLENGE_LEN
+        );
+
+        let malformed = WitnessOptions {
+            possession: Some((&possession, &[0x30, 0x00])),
+            ..Default::default()
+        };
+        assert!(witness(EE_CERT, layout, malformed).is_err());
+        assert!(witness(EE_CERT, layout, WitnessOptions::default()).is_err());
+        assert!(witness(EE_CERT, PublicInputsLayout::default(), options).is_err());
+    }
+
+    #[test]
+    fn test_generate_witness_with_coarse_now() {
+        let layout = PublicInputsLayout {
+            now_granularity: true,
+            ..Default::default()
+        };
+        let options = |rfc3339, now_granularity| WitnessOptions {
+            now: now(rfc3339),
+            now_granularity,
+            ..Default::default()
+        };
+
+        let values = witness(
+            CA_CERT,
+            layout,
+            options("2025-09-01T12:34:56Z", TimeGranularity::Day),
+        )
+        .unwrap();
+        assert_eq!(values.len(), plain_len(CA_CERT) + 1);
+        let now_start = values.len() - 8 - 4;
+        assert_eq!(
+            values[now_start..now_start + 8],
+            [0x07, 0xe9, 9, 1, 0, 0, 0, TimeGranularity::Day as u8]
+                .map(|b| FieldElement::from_repr(Fr::from(b)))
+        );
+
+        // the certificate is valid from 2025-08-21T17:27:04Z until 2025-09-16T15:20:10Z
+        for (rfc3339, granularity, valid) in [
+            ("2025-08-21T18:00:00Z", TimeGranularity::Hour, true),
+            ("2025-08-21T18:00:00Z", TimeGranularity::Day, false),
+            ("2025-09-16T15:10:00Z", TimeGranularity::Minute, true),
+            ("2025-09-16T15:10:00Z", TimeGranularity::Hour, false),
+        ] {
+            assert_eq!(
+                witness(CA_CERT, layout, options(rfc3339, granularity)).is_ok(),
+                valid,
+                "{} {:?}",
+                rfc3339,
+                granularity
+            );
+        }
+        assert!(
+            witness(
+                CA_CERT,
+                PublicInputsLayout::default(),
+                options("2025-09-01T12:34:56Z", TimeGranularity::Day)
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_generate_witness_with_path_len() {
+        let layout = PublicInputsLayout {
+            path_len: true,
+            ..Default::default()
+        };
+        let plain = plain_len(CA_CERT);
+        let values = witness(CA_CERT, layout, WitnessOptions::default()).unwrap();
+        assert_eq!(values.len(), plain + 1);
+        // the CA certificate has no pathLenConstraint
+        assert_eq!(
+            values[plain],
+            FieldElement::from_repr(Fr::from(NO_PATH_LEN))
+        );
+
+        assert!(witness(EE_CERT, layout, WitnessOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_generate_witness_with_revocation() {
+        let layout = PublicInputsLayout {
+            revocation: true,
+            ..Default::default()
+        };
+        let revocation = |tree| WitnessOptions {
+            revocation: Some(tree),
+            ..Default::default()
+        };
+        let plain = plain_len(EE_CERT);
+
+        // the serial number of the certificate is 1, so the range is (0, 2 + 1)
+        let mut list = RevocationList::new();
+        list.add(&[0x02]);
+        let tree = RevocationTree::new(&list, 4).unwrap();
+        let values = witness(EE_CERT, layout, revocation(&tree)).unwrap();
+        assert_eq!(values.len(), plain + 4 + 4);
+        assert_eq!(values[plain], FieldElement::from_repr(tree.root()));
+        assert_eq!(
+            values[plain + 1..plain + 4],
+            [0u32, 3, 0].map(|v| FieldElement::from_repr(Fr::from(v)))
+        );
+
+        list.add(&[0x01]);
+        let tree = RevocationTree::new(&list, 4).unwrap();
+        assert!(witness(EE_CERT, layout, revocation(&tree)).is_err());
+        assert!(witness(EE_CERT, layout, WitnessOptions::default()).is_err());
+        assert!(witness(EE_CERT, PublicInputsLayout::default(), revocation(&tree)).is_err());
+    }
+
+    #[test]
+    fn test_generate_witness_with_anchor_set() {
+        let layout = PublicInputsLayout {
+            anchor_set: true,
+            ..Default::default()
+        };
+        let with_set = |anchor_set| WitnessOptions {
+            anchor_set: Some(anchor_set),
+            ..Default::default()
+        };
+        let plain = plain_len(EE_CERT);
+
+        let mut store = TrustAnchorStore::new();
+        store.load_file("test_data/test_root.der").unwrap();
+        store.load_file("test_data/es256_ca.der").unwrap();
+        let anchor_set = store.anchor_set(3).unwrap();
+        let values = witness(EE_CERT, layout, with_set(&anchor_set)).unwrap();
+        // prev_cmt and prev_cmt_r are replaced with the root, commitment, index and path
+        assert_eq!(values.len(), plain - 2 + 3 + 3);
+        let root_start = plain - 4;
+        assert_eq!(
+            values[root_start],
+            FieldElement::from_repr(anchor_set.root())
+        );
+        assert_eq!(
+            values[root_start + 2],
+            FieldElement::from_repr(Fr::from(1u32))
+        );
+
+        let other_set = AnchorSet::new(store.get("test_root"), 3).unwrap();
+        assert!(witness(EE_CERT, layout, with_set(&other_set)).is_err());
+        assert!(witness(EE_CERT, layout, WitnessOptions::default()).is_err());
+        assert!(
+            witness(
+                EE_CERT,
+                PublicInputsLayout::default(),
+                with_set(&anchor_set)
+            )
+            .is_err()
+        );
+    }
+}
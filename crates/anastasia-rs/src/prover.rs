@@ -0,0 +1,157 @@
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    anchors::AnchorSet,
+    api::{
+        self, AttestationRequest, ChainLink, ChainProofResult, ChainVerificationError,
+        IssuerInputs, ProofResult, ProveOptions, VerifiedChain, VerifiedPublicInputs,
+    },
+    circuit::Circuit,
+    registry::{CircuitDescriptor, CircuitRegistry},
+};
+
+/// Long-lived handle on the circuits of a registry, which loads each circuit once and reuses it
+/// across proofs. The free functions of [`crate::api`] re-read the circuit JSON and VK on every
+/// call instead.
+pub struct Prover {
+    registry: CircuitRegistry,
+    circuits: Mutex<HashMap<String, Arc<Circuit>>>,
+}
+
+impl Prover {
+    pub fn new(registry: CircuitRegistry) -> Self {
+        Prover {
+            registry,
+            circuits: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Prover over the circuits of the manifest in `dir`, see [`CircuitRegistry::from_dir`]
+    pub fn from_dir(dir: impl AsRef<Path>) -> Result<Self, String> {
+        Ok(Self::new(CircuitRegistry::from_dir(dir)?))
+    }
+
+    pub fn registry(&self) -> &CircuitRegistry {
+        &self.registry
+    }
+
+    /// Loads every registered circuit ahead of the first proof. The largest circuits are
+    /// loaded first, so that the SRS is set up once for all of them.
+    pub fn warm_up(&self) -> Result<(), String> {
+        let mut descriptors: Vec<_> = self.registry.descriptors().collect();
+        descriptors.sort_by_key(|descriptor| Reverse(descriptor.size()));
+        for descriptor in descriptors {
+            self.load(descriptor)?;
+        }
+        Ok(())
+    }
+
+    /// The circuit `id`, loaded on first use
+    pub fn circuit(&self, id: &str) -> Result<Arc<Circuit>, String> {
+        let descriptor = self
+            .registry
+            .descriptor(id)
+            .ok_or_else(|| format!("unknown circuit `{}`", id))?;
+        self.load(descriptor)
+    }
+
+    fn load(&self, descriptor: &CircuitDescriptor) -> Result<Arc<Circuit>, String> {
+        let mut circuits = self.circuits.lock().unwrap();
+        if let Some(circuit) = circuits.get(&descriptor.meta.id) {
+            return Ok(circuit.clone());
+        }
+        let circuit = Circuit::new(&descriptor.meta)?;
+        descriptor.check(&circuit)?;
+        let circuit = Arc::new(circuit);
+        circuits.insert(descriptor.meta.id.clone(), circuit.clone());
+        Ok(circuit)
+    }
+
+    /// [`api::prove`] with the circuit `circuit_id`
+    pub fn prove(
+        &self,
+        circuit_id: &str,
+        cert: Vec<u8>,
+        issuer: &IssuerInputs,
+        options: ProveOptions,
+    ) -> Result<ProofResult, String> {
+        api::prove_with_circuit(&*self.circuit(circuit_id)?, cert, issuer, options)
+    }
+
+    /// [`api::prove_chain`] over the circuits of this prover
+    pub fn prove_chain(
+        &self,
+        chain: Vec<Vec<u8>>,
+        anchor_set: Option<&AnchorSet>,
+        options: ProveOptions,
+    ) -> Result<ChainProofResult, String> {
+        api::prove_chain_with(
+            |cert, variant| self.load(self.registry.select_descriptor(cert, variant)?),
+            chain,
+            anchor_set,
+            options,
+        )
+    }
+
+    /// [`api::verify`] with the circuit `circuit_id`
+    pub fn verify(
+        &self,
+        circuit_id: &str,
+        proof: Vec<u8>,
+        attestation: Option<AttestationRequest>,
+    ) -> Result<VerifiedPublicInputs, String> {
+        api::verify_with_circuit(&*self.circuit(circuit_id)?, proof, attestation)
+    }
+
+    /// [`api::verify_chain`] over the circuits of this prover
+    pub fn verify_chain(
+        &self,
+        anchor_cmt: &str,
+        proofs: &[ChainLink],
+        attestation: Option<&AttestationRequest>,
+    ) -> Result<VerifiedChain, ChainVerificationError> {
+        api::verify_chain_with(
+            |link, circuit_id| {
+                let descriptor = self.registry.descriptor(circuit_id).ok_or_else(|| {
+                    ChainVerificationError::UnknownCircuit {
+                        link,
+                        circuit_id: circuit_id.to_string(),
+                    }
+                })?;
+                let circuit = self
+                    .load(descriptor)
+                    .map_err(|reason| ChainVerificationError::InvalidProof { link, reason })?;
+                Ok((circuit, descriptor.role))
+            },
+            anchor_cmt,
+            proofs,
+            attestation,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prover_caches_circuits() {
+        let prover = Prover::from_dir("data").unwrap();
+        assert!(prover.circuit("es256_unknown").is_err());
+
+        let circuit = prover.circuit("es256_ca").unwrap();
+        assert!(Arc::ptr_eq(&circuit, &prover.circuit("es256_ca").unwrap()));
+
+        prover.warm_up().unwrap();
+        assert_eq!(
+            prover.circuits.lock().unwrap().len(),
+            prover.registry().descriptors().count()
+        );
+        assert!(Arc::ptr_eq(&circuit, &prover.circuit("es256_ca").unwrap()));
+    }
+}
@@ -0,0 +1,954 @@
+use ark_bn254::Fr;
+use ark_ff::{AdditiveGroup, BigInteger, PrimeField};
+use chrono::{DateTime, Utc};
+
+use crate::{
+    key_description::{SecurityLevel, VerifiedBootState},
+    predicate::{Predicate, PredicateAttribute, PredicateResult},
+    utils::{TimeGranularity, UtcTime},
+};
+
+const FIELD_LEN: usize = 32;
+
+/// `path_len` of a CA certificate without pathLenConstraint, as `verify::NO_PATH_LEN` in the
+/// circuits
+pub const NO_PATH_LEN: u8 = u8::MAX;
+
+/// Public inputs shared by all circuits, in the order they precede the proof:
+/// `now` as seven byte-sized fields (year1, year2, month, day, hour, minute, second),
+/// `now_granularity` if the circuit supports a coarse `now`, followed by `prev_cmt` and
+/// `next_cmt`, `path_len` if the circuit proves a CA certificate, and `revocation_root` if the
+/// circuit proves non-revocation.
+/// Circuits disclosing attestation properties append an [`AttestationDisclosure`], and circuits
+/// computing a pseudonym or a nullifier append a [`Nym`] or a [`Nullifier`], and circuits
+/// proving possession of the end-entity key append a [`Possession`], in that order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicInputs {
+    pub now: DateTime<Utc>,
+    pub now_granularity: Option<TimeGranularity>,
+    /// The anchor-set root instead if `anchor_set` is set
+    pub prev_cmt: Fr,
+    /// Whether the circuit proves that the chain starts from a member of the
+    /// [`AnchorSet`](crate::AnchorSet) with the root `prev_cmt`
+    pub anchor_set: bool,
+    pub next_cmt: Fr,
+    /// pathLenConstraint of a CA certificate, or [`NO_PATH_LEN`] if it has none
+    pub path_len: Option<u8>,
+    /// Root of the [`RevocationTree`](crate::RevocationTree) the certificate is not revoked in
+    pub revocation_root: Option<Fr>,
+    pub attestation: Option<AttestationDisclosure>,
+    pub nym: Option<Nym>,
+    pub nullifier: Option<Nullifier>,
+    pub possession: Option<Possession>,
+}
+
+/// The optional public inputs of a circuit: `now_granularity` following `now`, and `path_len`,
+/// `revocation_root` and the sections appended after `next_cmt`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PublicInputsLayout {
+    pub now_granularity: bool,
+    /// `prev_cmt` is the root of an anchor set; this does not change the encoding
+    pub anchor_set: bool,
+    pub path_len: bool,
+    pub revocation: bool,
+    pub attestation: bool,
+    pub nym: bool,
+    pub nullifier: bool,
+    pub possession: bool,
+}
+
+impl PublicInputsLayout {
+    pub fn encoded_len(&self) -> usize {
+        let mut len = PublicInputs::LEN;
+        if self.now_granularity {
+            len += FIELD_LEN;
+        }
+        if self.path_len {
+            len += FIELD_LEN;
+        }
+        if self.revocation {
+            len += FIELD_LEN;
+        }
+        if self.attestation {
+            len += AttestationDisclosure::LEN;
+        }
+        if self.nym {
+            len += Nym::LEN;
+        }
+        if self.nullifier {
+            len += Nullifier::LEN;
+        }
+        if self.possession {
+            len += Possession::LEN;
+        }
+        len
+    }
+}
+
+/// Context-specific pseudonym `nym` of the end-entity public key, see
+/// [`nym`](crate::utils::nym). Only proofs for the same `ctx` have comparable nyms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Nym {
+    pub ctx: Fr,
+    pub nym: Fr,
+}
+
+impl Nym {
+    pub const NUM_FIELDS: usize = 2;
+    pub const LEN: usize = Self::NUM_FIELDS * FIELD_LEN;
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = field_to_be_bytes(&self.ctx);
+        bytes.extend(field_to_be_bytes(&self.nym));
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() != Self::LEN {
+            return Err(format!("nym must be {} bytes", Self::LEN));
+        }
+        Ok(Nym {
+            ctx: field_from_be_bytes(&bytes[..FIELD_LEN], "ctx")?,
+            nym: field_from_be_bytes(&bytes[FIELD_LEN..], "nym")?,
+        })
+    }
+}
+
+/// Nullifier of the end-entity public key for a `scope` and `epoch`, see
+/// [`nullifier`](crate::utils::nullifier). A device yields one nullifier per scope and epoch as
+/// long as it keeps its secret, so verifiers reject repeated ones with a
+/// [`NullifierStore`](crate::nullifier::NullifierStore).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Nullifier {
+    pub scope: Fr,
+    pub epoch: u64,
+    pub nullifier: Fr,
+}
+
+impl Nullifier {
+    pub const NUM_FIELDS: usize = 3;
+    pub const LEN: usize = Self::NUM_FIELDS * FIELD_LEN;
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = field_to_be_bytes(&self.scope);
+        bytes.extend(u64_to_field(self.epoch));
+        bytes.extend(field_to_be_bytes(&self.nullifier));
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() != Self::LEN {
+            return Err(format!("nullifier must be {} bytes", Self::LEN));
+        }
+        let mut fields = bytes.chunks_exact(FIELD_LEN);
+        Ok(Nullifier {
+            scope: field_from_be_bytes(fields.next().unwrap(), "scope")?,
+            epoch: u64_from_field(fields.next().unwrap(), "epoch")?,
+            nullifier: field_from_be_bytes(fields.next().unwrap(), "nullifier")?,
+        })
+    }
+}
+
+/// Verifier challenge `pop_challenge` signed by the end-entity key, as byte-sized fields. The
+/// proof only shows possession of the key if the verifier issued the challenge for this session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Possession {
+    pub challenge: [u8; Possession::CHALLENGE_LEN],
+}
+
+impl Possession {
+    pub const CHALLENGE_LEN: usize = 32;
+    pub const NUM_FIELDS: usize = Self::CHALLENGE_LEN;
+    pub const LEN: usize = Self::NUM_FIELDS * FIELD_LEN;
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.challenge
+            .iter()
+            .flat_map(|b| byte_to_field(*b))
+            .collect()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() != Self::LEN {
+            return Err(format!("possession challenge must be {} bytes", Self::LEN));
+        }
+        let mut challenge = [0u8; Self::CHALLENGE_LEN];
+        for (byte, field) in challenge.iter_mut().zip(bytes.chunks_exact(FIELD_LEN)) {
+            *byte = byte_from_field(field, "pop_challenge")?;
+        }
+        Ok(Possession { challenge })
+    }
+}
+
+/// Key attestation properties disclosed by the `es256_ee_attested` circuit, as byte-sized
+/// fields: attestationSecurityLevel, deviceLocked and verifiedBootState, followed by
+/// attestationChallenge zero-padded to `MAX_CHALLENGE_LEN` bytes and its length, and then the
+/// tags, thresholds and results of `MAX_PREDICATES` predicate slots (tag 0 if unused), the
+/// app id hash (0 if not disclosed) and an allow-list of `MAX_ALLOWED_APPS` app id hashes the
+/// attesting app was proved to match (zero-padded, empty if none).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttestationDisclosure {
+    pub security_level: SecurityLevel,
+    pub device_locked: bool,
+    pub verified_boot_state: VerifiedBootState,
+    pub challenge: Vec<u8>,
+    pub predicates: Vec<PredicateResult>,
+    /// See [`app_id_hash`](crate::utils::app_id_hash)
+    pub app_id_hash: Option<Fr>,
+    pub app_id_allow_list: Vec<Fr>,
+}
+
+impl AttestationDisclosure {
+    pub const MAX_CHALLENGE_LEN: usize = 32;
+    pub const MAX_PREDICATES: usize = 4;
+    pub const MAX_ALLOWED_APPS: usize = 8;
+    pub const NUM_FIELDS: usize =
+        3 + Self::MAX_CHALLENGE_LEN + 1 + 3 * Self::MAX_PREDICATES + 1 + Self::MAX_ALLOWED_APPS;
+    pub const LEN: usize = Self::NUM_FIELDS * FIELD_LEN;
+
+    /// Whether `predicate` was proved to hold, or `None` if it was not proved at all.
+    pub fn holds(&self, predicate: &Predicate) -> Option<bool> {
+        self.predicates
+            .iter()
+            .find(|result| result.predicate == *predicate)
+            .map(|result| result.holds)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(Self::LEN);
+        bytes.extend(byte_to_field(self.security_level as u8));
+        bytes.extend(byte_to_field(self.device_locked as u8));
+        bytes.extend(byte_to_field(self.verified_boot_state as u8));
+        for i in 0..Self::MAX_CHALLENGE_LEN {
+            bytes.extend(byte_to_field(self.challenge.get(i).copied().unwrap_or(0)));
+        }
+        bytes.extend(byte_to_field(self.challenge.len() as u8));
+
+        let slot = |i: usize| self.predicates.get(i);
+        for i in 0..Self::MAX_PREDICATES {
+            bytes.extend(u64_to_field(
+                slot(i).map_or(0, |r| r.predicate.attribute.tag() as u64),
+            ));
+        }
+        for i in 0..Self::MAX_PREDICATES {
+            bytes.extend(u64_to_field(slot(i).map_or(0, |r| r.predicate.threshold)));
+        }
+        for i in 0..Self::MAX_PREDICATES {
+            bytes.extend(byte_to_field(slot(i).is_some_and(|r| r.holds) as u8));
+        }
+        bytes.extend(field_to_be_bytes(&self.app_id_hash.unwrap_or(Fr::ZERO)));
+        for i in 0..Self::MAX_ALLOWED_APPS {
+            let hash = self.app_id_allow_list.get(i).copied().unwrap_or(Fr::ZERO);
+            bytes.extend(field_to_be_bytes(&hash));
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() != Self::LEN {
+            return Err(format!(
+                "attestation disclosure must be {} bytes",
+                Self::LEN
+            ));
+        }
+        let mut fields = bytes.chunks_exact(FIELD_LEN);
+
+        let security_level = byte_from_field(fields.next().unwrap(), "attestation_security_level")?;
+        let device_locked = bool_from_field(fields.next().unwrap(), "device_locked")?;
+        let verified_boot_state = byte_from_field(fields.next().unwrap(), "verified_boot_state")?;
+
+        let mut challenge = fields
+            .by_ref()
+            .take(Self::MAX_CHALLENGE_LEN)
+            .map(|field| byte_from_field(field, "attestation_challenge"))
+            .collect::<Result<Vec<_>, _>>()?;
+        let challenge_len =
+            byte_from_field(fields.next().unwrap(), "attestation_challenge_len")? as usize;
+        if challenge_len > Self::MAX_CHALLENGE_LEN
+            || challenge[challenge_len..].iter().any(|b| *b != 0)
+        {
+            return Err(
+                "public input `attestation_challenge` is not canonically padded".to_string(),
+            );
+        }
+        challenge.truncate(challenge_len);
+
+        let tags: Vec<_> = fields.by_ref().take(Self::MAX_PREDICATES).collect();
+        let thresholds: Vec<_> = fields.by_ref().take(Self::MAX_PREDICATES).collect();
+        let results: Vec<_> = fields.by_ref().take(Self::MAX_PREDICATES).collect();
+        let mut predicates = Vec::new();
+        for ((tag, threshold), holds) in tags.into_iter().zip(thresholds).zip(results) {
+            let tag = u64_from_field(tag, "predicate_tags")?;
+            let threshold = u64_from_field(threshold, "predicate_thresholds")?;
+            let holds = bool_from_field(holds, "predicate_results")?;
+            if tag == 0 {
+                if threshold != 0 || holds {
+                    return Err("unused predicate slot must be zero".to_string());
+                }
+                continue;
+            }
+            predicates.push(PredicateResult {
+                predicate: Predicate::new(PredicateAttribute::from_tag(tag as u32)?, threshold),
+                holds,
+            });
+        }
+
+        let app_id_hash = field_from_be_bytes(fields.next().unwrap(), "app_id_hash")?;
+        let mut app_id_allow_list = fields
+            .map(|field| field_from_be_bytes(field, "app_id_allow_list"))
+            .collect::<Result<Vec<_>, _>>()?;
+        let allow_list_len = app_id_allow_list
+            .iter()
+            .position(|hash| *hash == Fr::ZERO)
+            .unwrap_or(Self::MAX_ALLOWED_APPS);
+        if app_id_allow_list[allow_list_len..]
+            .iter()
+            .any(|hash| *hash != Fr::ZERO)
+        {
+            return Err("public input `app_id_allow_list` is not canonically padded".to_string());
+        }
+        app_id_allow_list.truncate(allow_list_len);
+
+        Ok(AttestationDisclosure {
+            security_level: SecurityLevel::from_u64(security_level as u64)?,
+            device_locked,
+            verified_boot_state: VerifiedBootState::from_u64(verified_boot_state as u64)?,
+            challenge,
+            predicates,
+            app_id_hash: (app_id_hash != Fr::ZERO).then_some(app_id_hash),
+            app_id_allow_list,
+        })
+    }
+}
+
+impl PublicInputs {
+    pub const NUM_FIELDS: usize = 9;
+    pub const LEN: usize = Self::NUM_FIELDS * FIELD_LEN;
+
+    pub fn layout(&self) -> PublicInputsLayout {
+        PublicInputsLayout {
+            now_granularity: self.now_granularity.is_some(),
+            anchor_set: self.anchor_set,
+            path_len: self.path_len.is_some(),
+            revocation: self.revocation_root.is_some(),
+            attestation: self.attestation.is_some(),
+            nym: self.nym.is_some(),
+            nullifier: self.nullifier.is_some(),
+            possession: self.possession.is_some(),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.layout().encoded_len());
+        for b in UtcTime::from_datetime(&self.now).to_bytes() {
+            bytes.extend(byte_to_field(b));
+        }
+        if let Some(granularity) = self.now_granularity {
+            bytes.extend(byte_to_field(granularity as u8));
+        }
+        bytes.extend(field_to_be_bytes(&self.prev_cmt));
+        bytes.extend(field_to_be_bytes(&self.next_cmt));
+        if let Some(path_len) = self.path_len {
+            bytes.extend(byte_to_field(path_len));
+        }
+        if let Some(revocation_root) = &self.revocation_root {
+            bytes.extend(field_to_be_bytes(revocation_root));
+        }
+        if let Some(attestation) = &self.attestation {
+            bytes.extend(attestation.to_bytes());
+        }
+        if let Some(nym) = &self.nym {
+            bytes.extend(nym.to_bytes());
+        }
+        if let Some(nullifier) = &self.nullifier {
+            bytes.extend(nullifier.to_bytes());
+        }
+        if let Some(possession) = &self.possession {
+            bytes.extend(possession.to_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8], layout: PublicInputsLayout) -> Result<Self, String> {
+        if bytes.len() != layout.encoded_len() {
+            return Err(format!(
+                "public inputs must be {} bytes",
+                layout.encoded_len()
+            ));
+        }
+        let core_len = Self::LEN + if layout.now_granularity { FIELD_LEN } else { 0 };
+        let mut fields = bytes[..core_len].chunks_exact(FIELD_LEN);
+
+        let mut now = [0u8; 7];
+        for (byte, field) in now.iter_mut().zip(fields.by_ref()) {
+            *byte = byte_from_field(field, "now")?;
+        }
+        let now = UtcTime::from_bytes(now).to_datetime()?;
+        let now_granularity = if layout.now_granularity {
+            let granularity = TimeGranularity::from_u64(u64_from_field(
+                fields.next().unwrap(),
+                "now_granularity",
+            )?)?;
+            if granularity.truncate(&now) != now {
+                return Err(format!(
+                    "public input `now` is not truncated to {:?}",
+                    granularity
+                ));
+            }
+            Some(granularity)
+        } else {
+            None
+        };
+
+        let prev_cmt = field_from_be_bytes(fields.next().unwrap(), "prev_cmt")?;
+        let next_cmt = field_from_be_bytes(fields.next().unwrap(), "next_cmt")?;
+
+        let mut rest = &bytes[core_len..];
+        let path_len = if layout.path_len {
+            let (path_len, tail) = rest.split_at(FIELD_LEN);
+            rest = tail;
+            Some(byte_from_field(path_len, "path_len")?)
+        } else {
+            None
+        };
+        let revocation_root = if layout.revocation {
+            let (root, tail) = rest.split_at(FIELD_LEN);
+            rest = tail;
+            Some(field_from_be_bytes(root, "revocation_root")?)
+        } else {
+            None
+        };
+        let attestation = if layout.attestation {
+            let (disclosure, tail) = rest.split_at(AttestationDisclosure::LEN);
+            rest = tail;
+            Some(AttestationDisclosure::from_bytes(disclosure)?)
+        } else {
+            None
+        };
+        let nym = if layout.nym {
+            let (nym, tail) = rest.split_at(Nym::LEN);
+            rest = tail;
+            Some(Nym::from_bytes(nym)?)
+        } else {
+            None
+        };
+        let nullifier = if layout.nullifier {
+            let (nullifier, tail) = rest.split_at(Nullifier::LEN);
+            rest = tail;
+            Some(Nullifier::from_bytes(nullifier)?)
+        } else {
+            None
+        };
+        let possession = if layout.possession {
+            Some(Possession::from_bytes(rest)?)
+        } else {
+            None
+        };
+
+        Ok(PublicInputs {
+            now,
+            now_granularity,
+            prev_cmt,
+            anchor_set: layout.anchor_set,
+            next_cmt,
+            path_len,
+            revocation_root,
+            attestation,
+            nym,
+            nullifier,
+            possession,
+        })
+    }
+}
+
+fn byte_to_field(b: u8) -> [u8; FIELD_LEN] {
+    u64_to_field(b as u64)
+}
+
+fn u64_to_field(v: u64) -> [u8; FIELD_LEN] {
+    let mut field = [0u8; FIELD_LEN];
+    field[FIELD_LEN - 8..].copy_from_slice(&v.to_be_bytes());
+    field
+}
+
+fn field_to_be_bytes(v: &Fr) -> Vec<u8> {
+    v.into_bigint().to_bytes_be()
+}
+
+fn byte_from_field(field: &[u8], name: &str) -> Result<u8, String> {
+    if field[..FIELD_LEN - 1].iter().any(|b| *b != 0) {
+        return Err(format!("public input `{}` must consist of bytes", name));
+    }
+    Ok(field[FIELD_LEN - 1])
+}
+
+fn bool_from_field(field: &[u8], name: &str) -> Result<bool, String> {
+    match byte_from_field(field, name)? {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(format!("public input `{}` must be a boolean", name)),
+    }
+}
+
+fn u64_from_field(field: &[u8], name: &str) -> Result<u64, String> {
+    if field[..FIELD_LEN - 8].iter().any(|b| *b != 0) {
+        return Err(format!("public input `{}` must be a 64-bit integer", name));
+    }
+    Ok(u64::from_be_bytes(
+        field[FIELD_LEN - 8..].try_into().unwrap(),
+    ))
+}
+
+fn field_from_be_bytes(bytes: &[u8], name: &str) -> Result<Fr, String> {
+    let v = Fr::from_be_bytes_mod_order(bytes);
+    if v.into_bigint().to_bytes_be() != bytes {
+        return Err(format!(
+            "public input `{}` is not a canonical field element",
+            name
+        ));
+    }
+    Ok(v)
+}
+
+/// Splits a proof returned by the prover into its public inputs and the bare proof bytes.
+pub fn split_proof(proof_with_public_inputs: &[u8]) -> Result<(PublicInputs, Vec<u8>), String> {
+    split_proof_with_layout(proof_with_public_inputs, PublicInputsLayout::default())
+}
+
+/// Like [`split_proof`], for proofs of a circuit disclosing attestation properties.
+pub fn split_attested_proof(
+    proof_with_public_inputs: &[u8],
+) -> Result<(PublicInputs, Vec<u8>), String> {
+    split_proof_with_layout(
+        proof_with_public_inputs,
+        PublicInputsLayout {
+            attestation: true,
+            ..Default::default()
+        },
+    )
+}
+
+/// Like [`split_proof`], for proofs of a circuit with the given [`PublicInputsLayout`].
+pub fn split_proof_with_layout(
+    proof_with_public_inputs: &[u8],
+    layout: PublicInputsLayout,
+) -> Result<(PublicInputs, Vec<u8>), String> {
+    let public_inputs_len = layout.encoded_len();
+    if proof_with_public_inputs.len() < public_inputs_len {
+        return Err(format!(
+            "proof must be at least {} bytes",
+            public_inputs_len
+        ));
+    }
+    let (public_inputs, proof) = proof_with_public_inputs.split_at(public_inputs_len);
+    Ok((
+        PublicInputs::from_bytes(public_inputs, layout)?,
+        proof.to_vec(),
+    ))
+}
+
+/// Inverse of [`split_proof`].
+pub fn join_proof(public_inputs: &PublicInputs, proof: &[u8]) -> Vec<u8> {
+    let mut proof_with_public_inputs = public_inputs.to_bytes();
+    proof_with_public_inputs.extend_from_slice(proof);
+    proof_with_public_inputs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{field_to_hex, hex_to_field};
+    use chrono::TimeZone;
+
+    const EXAMPLE_APP_ID: &str = "082fa0ba5ca89c1918e3cedccde7bb5968fab935d664c3e8ba68b267b995de74";
+
+    fn example_attested_proof() -> Vec<u8> {
+        let mut proof = example_proof();
+        let body = proof.split_off(PublicInputs::LEN);
+        let mut disclosed = vec![2, 1, 0, 0x0a, 0x0b];
+        disclosed.resize(3 + AttestationDisclosure::MAX_CHALLENGE_LEN, 0);
+        disclosed.push(2); // challenge length
+        disclosed.extend([706, 701, 0, 0]); // predicate tags
+        disclosed.extend([202405, 0x019903ec61ba, 0, 0]); // predicate thresholds
+        disclosed.extend([1, 0, 0, 0]); // predicate results
+        for v in disclosed {
+            let mut field = [0u8; 32];
+            field[24..].copy_from_slice(&u64::to_be_bytes(v));
+            proof.extend_from_slice(&field);
+        }
+        proof.extend([0u8; 32]); // app id hash
+        proof.extend(hex::decode(EXAMPLE_APP_ID).unwrap()); // app id allow-list
+        proof.extend([0u8; 32 * (AttestationDisclosure::MAX_ALLOWED_APPS - 1)]);
+        proof.extend(body);
+        proof
+    }
+
+    fn example_proof() -> Vec<u8> {
+        let mut proof = Vec::new();
+        for b in [0x07, 0xe9, 9, 14, 13, 18, 39] {
+            let mut field = [0u8; 32];
+            field[31] = b;
+            proof.extend_from_slice(&field);
+        }
+        proof.extend(
+            hex::decode("0ede28f511104f08069e07986707873be5cbba917f02f02407ad1fdd6838679b")
+                .unwrap(),
+        );
+        proof.extend(
+            hex::decode("19d232e47b0b55d2590ccb04205c088ecfb0cd715c1d49ec4dffb0e097fd9d8e")
+                .unwrap(),
+        );
+        proof.extend_from_slice(&[0xab; 64]); // proof body
+        proof
+    }
+
+    #[test]
+    fn test_split_proof() {
+        let (public_inputs, proof) = split_proof(&example_proof()).unwrap();
+        assert_eq!(
+            public_inputs.now,
+            Utc.with_ymd_and_hms(2025, 9, 14, 13, 18, 39).unwrap()
+        );
+        assert_eq!(
+            public_inputs.prev_cmt,
+            hex_to_field("0ede28f511104f08069e07986707873be5cbba917f02f02407ad1fdd6838679b")
+                .unwrap()
+        );
+        assert_eq!(
+            field_to_hex(&public_inputs.next_cmt),
+            "19d232e47b0b55d2590ccb04205c088ecfb0cd715c1d49ec4dffb0e097fd9d8e"
+        );
+        assert_eq!(proof, vec![0xab; 64]);
+    }
+
+    #[test]
+    fn test_join_proof() {
+        let proof_with_public_inputs = example_proof();
+        let (public_inputs, proof) = split_proof(&proof_with_public_inputs).unwrap();
+        assert_eq!(join_proof(&public_inputs, &proof), proof_with_public_inputs);
+    }
+
+    #[test]
+    fn test_split_proof_rejects_invalid_public_inputs() {
+        let mut proof = example_proof();
+        proof[30] = 0x01; // year1 is not a single byte
+        assert!(split_proof(&proof).is_err());
+
+        let mut proof = example_proof();
+        proof[3 * 32 - 1] = 0; // month 0
+        assert!(split_proof(&proof).is_err());
+
+        let mut proof = example_proof();
+        proof[7 * 32..8 * 32].copy_from_slice(&[0xff; 32]); // prev_cmt >= modulus
+        assert!(split_proof(&proof).is_err());
+
+        assert!(split_proof(&[0u8; PublicInputs::LEN - 1]).is_err());
+    }
+
+    #[test]
+    fn test_split_attested_proof() {
+        let proof_with_public_inputs = example_attested_proof();
+        let (public_inputs, proof) = split_attested_proof(&proof_with_public_inputs).unwrap();
+        assert_eq!(
+            public_inputs.now,
+            Utc.with_ymd_and_hms(2025, 9, 14, 13, 18, 39).unwrap()
+        );
+        assert_eq!(
+            public_inputs.attestation,
+            Some(AttestationDisclosure {
+                security_level: SecurityLevel::StrongBox,
+                device_locked: true,
+                verified_boot_state: VerifiedBootState::Verified,
+                challenge: vec![0x0a, 0x0b],
+                predicates: vec![
+                    PredicateResult {
+                        predicate: Predicate::new(PredicateAttribute::OsPatchLevel, 202405),
+                        holds: true,
+                    },
+                    PredicateResult {
+                        predicate: Predicate::new(
+                            PredicateAttribute::CreationDateTime,
+                            0x019903ec61ba
+                        ),
+                        holds: false,
+                    },
+                ],
+                app_id_hash: None,
+                app_id_allow_list: vec![hex_to_field(EXAMPLE_APP_ID).unwrap()],
+            })
+        );
+        let attestation = public_inputs.attestation.as_ref().unwrap();
+        assert_eq!(
+            attestation.holds(&Predicate::new(PredicateAttribute::OsPatchLevel, 202405)),
+            Some(true)
+        );
+        assert_eq!(
+            attestation.holds(&Predicate::new(PredicateAttribute::OsPatchLevel, 202406)),
+            None
+        );
+        assert_eq!(proof, vec![0xab; 64]);
+        assert_eq!(join_proof(&public_inputs, &proof), proof_with_public_inputs);
+
+        let (public_inputs, _) = split_proof(&example_proof()).unwrap();
+        assert!(public_inputs.attestation.is_none());
+    }
+
+    #[test]
+    fn test_split_attested_proof_rejects_invalid_disclosure() {
+        let mut proof = example_attested_proof();
+        proof[PublicInputs::LEN + 31] = 3; // unknown security level
+        assert!(split_attested_proof(&proof).is_err());
+
+        let mut proof = example_attested_proof();
+        proof[PublicInputs::LEN + 2 * 32 - 1] = 0xff; // device_locked is not a boolean
+        assert!(split_attested_proof(&proof).is_err());
+
+        let mut proof = example_attested_proof();
+        proof[PublicInputs::LEN + 2 * 32] = 0x01; // verified_boot_state is not a byte
+        assert!(split_attested_proof(&proof).is_err());
+
+        let mut proof = example_attested_proof();
+        proof[PublicInputs::LEN + 6 * 32 - 1] = 0x0c; // challenge byte beyond its length
+        assert!(split_attested_proof(&proof).is_err());
+
+        let challenge_len_end =
+            PublicInputs::LEN + (3 + AttestationDisclosure::MAX_CHALLENGE_LEN + 1) * 32;
+        let mut proof = example_attested_proof();
+        proof[challenge_len_end - 1] = 33; // challenge length
+        assert!(split_attested_proof(&proof).is_err());
+
+        let mut proof = example_attested_proof();
+        proof[challenge_len_end + 3 * 32 - 1] = 3; // keySize is not a predicate tag
+        assert!(split_attested_proof(&proof).is_err());
+
+        let mut proof = example_attested_proof();
+        proof[challenge_len_end + 7 * 32 - 1] = 1; // threshold of an unused slot
+        assert!(split_attested_proof(&proof).is_err());
+
+        let mut proof = example_attested_proof();
+        proof[challenge_len_end + 8 * 32 + 23] = 1; // result is not a boolean
+        assert!(split_attested_proof(&proof).is_err());
+
+        let app_id_end = challenge_len_end + 13 * 32;
+        let mut proof = example_attested_proof();
+        proof[app_id_end - 32..app_id_end].copy_from_slice(&[0xff; 32]); // app id >= modulus
+        assert!(split_attested_proof(&proof).is_err());
+
+        let mut proof = example_attested_proof();
+        proof[app_id_end + 3 * 32 - 1] = 1; // allow-list entry after a zero entry
+        assert!(split_attested_proof(&proof).is_err());
+    }
+
+    #[test]
+    fn test_split_proof_with_nym() {
+        let nym = Nym {
+            ctx: Fr::from(1u64),
+            nym: hex_to_field("169cf2496642b471ab008314ab38b600845185c0527591850a73ab158cac17fe")
+                .unwrap(),
+        };
+        let layout = PublicInputsLayout {
+            nym: true,
+            ..Default::default()
+        };
+        let mut proof_with_public_inputs = example_proof();
+        let body = proof_with_public_inputs.split_off(PublicInputs::LEN);
+        proof_with_public_inputs.extend(nym.to_bytes());
+        proof_with_public_inputs.extend(body);
+
+        let (public_inputs, proof) =
+            split_proof_with_layout(&proof_with_public_inputs, layout).unwrap();
+        assert_eq!(public_inputs.nym, Some(nym));
+        assert!(public_inputs.attestation.is_none());
+        assert_eq!(public_inputs.layout(), layout);
+        assert_eq!(proof, vec![0xab; 64]);
+        assert_eq!(join_proof(&public_inputs, &proof), proof_with_public_inputs);
+
+        // the nym follows the attestation disclosure
+        let mut attested = split_attested_proof(&example_attested_proof()).unwrap().0;
+        attested.nym = Some(nym);
+        let proof_with_public_inputs = join_proof(&attested, &proof);
+        let layout = PublicInputsLayout {
+            attestation: true,
+            nym: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            split_proof_with_layout(&proof_with_public_inputs, layout)
+                .unwrap()
+                .0,
+            attested
+        );
+    }
+
+    #[test]
+    fn test_split_proof_with_nullifier() {
+        let nullifier = Nullifier {
+            scope: Fr::from(1u64),
+            epoch: 20250901,
+            nullifier: hex_to_field(
+                "0d5cfbaf71d4d08bb3df03768418852e36eca2d719bde2ed9674814b951c8ed4",
+            )
+            .unwrap(),
+        };
+        let layout = PublicInputsLayout {
+            nullifier: true,
+            ..Default::default()
+        };
+        let mut proof_with_public_inputs = example_proof();
+        let body = proof_with_public_inputs.split_off(PublicInputs::LEN);
+        proof_with_public_inputs.extend(nullifier.to_bytes());
+        proof_with_public_inputs.extend(body);
+
+        let (public_inputs, proof) =
+            split_proof_with_layout(&proof_with_public_inputs, layout).unwrap();
+        assert_eq!(public_inputs.nullifier, Some(nullifier));
+        assert!(public_inputs.nym.is_none());
+        assert_eq!(public_inputs.layout(), layout);
+        assert_eq!(join_proof(&public_inputs, &proof), proof_with_public_inputs);
+
+        // an epoch beyond u64 is rejected
+        let epoch_start = PublicInputs::LEN + FIELD_LEN;
+        proof_with_public_inputs[epoch_start] = 1;
+        assert!(split_proof_with_layout(&proof_with_public_inputs, layout).is_err());
+    }
+
+    #[test]
+    fn test_split_proof_with_possession() {
+        let mut challenge = [0u8; Possession::CHALLENGE_LEN];
+        for (i, b) in challenge.iter_mut().enumerate() {
+            *b = i as u8 + 1;
+        }
+        let possession = Possession { challenge };
+        let layout = PublicInputsLayout {
+            possession: true,
+            ..Default::default()
+        };
+        let mut proof_with_public_inputs = example_proof();
+        let body = proof_with_public_inputs.split_off(PublicInputs::LEN);
+        proof_with_public_inputs.extend(possession.to_bytes());
+        proof_with_public_inputs.extend(body);
+
+        let (public_inputs, proof) =
+            split_proof_with_layout(&proof_with_public_inputs, layout).unwrap();
+        assert_eq!(public_inputs.possession, Some(possession));
+        assert_eq!(public_inputs.layout(), layout);
+        assert_eq!(join_proof(&public_inputs, &proof), proof_with_public_inputs);
+
+        // a challenge field must be a byte
+        proof_with_public_inputs[PublicInputs::LEN + FIELD_LEN - 2] = 1;
+        assert!(split_proof_with_layout(&proof_with_public_inputs, layout).is_err());
+    }
+
+    #[test]
+    fn test_split_proof_with_now_granularity() {
+        let layout = PublicInputsLayout {
+            now_granularity: true,
+            ..Default::default()
+        };
+        let with_granularity = |now: [u64; 7], granularity: u64| {
+            let mut proof = Vec::new();
+            for v in now.into_iter().chain([granularity]) {
+                proof.extend(u64_to_field(v));
+            }
+            proof.extend(example_proof().split_off(7 * FIELD_LEN));
+            proof
+        };
+
+        let proof_with_public_inputs = with_granularity([0x07, 0xe9, 9, 14, 13, 0, 0], 2);
+        let (public_inputs, proof) =
+            split_proof_with_layout(&proof_with_public_inputs, layout).unwrap();
+        assert_eq!(
+            public_inputs.now,
+            Utc.with_ymd_and_hms(2025, 9, 14, 13, 0, 0).unwrap()
+        );
+        assert_eq!(public_inputs.now_granularity, Some(TimeGranularity::Hour));
+        assert_eq!(
+            field_to_hex(&public_inputs.next_cmt),
+            "19d232e47b0b55d2590ccb04205c088ecfb0cd715c1d49ec4dffb0e097fd9d8e"
+        );
+        assert_eq!(public_inputs.layout(), layout);
+        assert_eq!(join_proof(&public_inputs, &proof), proof_with_public_inputs);
+
+        // `now` must be truncated to the granularity
+        let proof = with_granularity([0x07, 0xe9, 9, 14, 13, 18, 0], 2);
+        assert!(split_proof_with_layout(&proof, layout).is_err());
+        let proof = with_granularity([0x07, 0xe9, 9, 14, 13, 0, 0], 4);
+        assert!(split_proof_with_layout(&proof, layout).is_err());
+    }
+
+    #[test]
+    fn test_split_proof_with_revocation_root() {
+        let layout = PublicInputsLayout {
+            revocation: true,
+            nym: true,
+            ..Default::default()
+        };
+        let root = hex_to_field("301b284afa3f7eee790ad039b37d97b4926537a9c70d4828e897a4c61e41566c")
+            .unwrap();
+        let nym = Nym {
+            ctx: Fr::from(1u64),
+            nym: Fr::from(2u64),
+        };
+        let mut proof_with_public_inputs = example_proof();
+        let body = proof_with_public_inputs.split_off(PublicInputs::LEN);
+        proof_with_public_inputs.extend(field_to_be_bytes(&root));
+        proof_with_public_inputs.extend(nym.to_bytes());
+        proof_with_public_inputs.extend(body);
+
+        let (public_inputs, proof) =
+            split_proof_with_layout(&proof_with_public_inputs, layout).unwrap();
+        assert_eq!(public_inputs.revocation_root, Some(root));
+        assert_eq!(public_inputs.nym, Some(nym));
+        assert_eq!(public_inputs.layout(), layout);
+        assert_eq!(join_proof(&public_inputs, &proof), proof_with_public_inputs);
+
+        // the root must be a field element
+        proof_with_public_inputs[PublicInputs::LEN..PublicInputs::LEN + FIELD_LEN]
+            .copy_from_slice(&[0xff; FIELD_LEN]);
+        assert!(split_proof_with_layout(&proof_with_public_inputs, layout).is_err());
+    }
+
+    #[test]
+    fn test_split_proof_with_path_len() {
+        let layout = PublicInputsLayout {
+            path_len: true,
+            revocation: true,
+            ..Default::default()
+        };
+        let root = Fr::from(3u64);
+        let mut proof_with_public_inputs = example_proof();
+        let body = proof_with_public_inputs.split_off(PublicInputs::LEN);
+        proof_with_public_inputs.extend(byte_to_field(NO_PATH_LEN));
+        proof_with_public_inputs.extend(field_to_be_bytes(&root));
+        proof_with_public_inputs.extend(body);
+
+        let (public_inputs, proof) =
+            split_proof_with_layout(&proof_with_public_inputs, layout).unwrap();
+        assert_eq!(public_inputs.path_len, Some(NO_PATH_LEN));
+        assert_eq!(public_inputs.revocation_root, Some(root));
+        assert_eq!(public_inputs.layout(), layout);
+        assert_eq!(join_proof(&public_inputs, &proof), proof_with_public_inputs);
+
+        // path_len must be a byte
+        proof_with_public_inputs[PublicInputs::LEN + FIELD_LEN - 2] = 1;
+        assert!(split_proof_with_layout(&proof_with_public_inputs, layout).is_err());
+    }
+
+    #[test]
+    fn test_split_proof_with_anchor_set() {
+        let layout = PublicInputsLayout {
+            anchor_set: true,
+            ..Default::default()
+        };
+        let proof_with_public_inputs = example_proof();
+        let (public_inputs, proof) =
+            split_proof_with_layout(&proof_with_public_inputs, layout).unwrap();
+        assert!(public_inputs.anchor_set);
+        assert_eq!(
+            public_inputs.prev_cmt,
+            split_proof(&proof_with_public_inputs).unwrap().0.prev_cmt
+        );
+        assert_eq!(public_inputs.layout(), layout);
+        assert_eq!(join_proof(&public_inputs, &proof), proof_with_public_inputs);
+    }
+}
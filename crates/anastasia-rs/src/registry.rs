@@ -0,0 +1,531 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde_json::Value;
+
+use crate::{
+    cert::{ParsedCert, SignatureAlgorithm},
+    circuit::{Circuit, CircuitMeta},
+};
+
+/// File names looked up by [`CircuitRegistry::from_dir`], in order
+const MANIFEST_FILE_NAMES: [&str; 2] = ["manifest.json", "manifest.toml"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitRole {
+    Ca,
+    Ee,
+}
+
+impl CircuitRole {
+    pub fn of(parsed_cert: &ParsedCert) -> Self {
+        if parsed_cert.basic_constraints_ca {
+            CircuitRole::Ca
+        } else {
+            CircuitRole::Ee
+        }
+    }
+
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "ca" => Ok(CircuitRole::Ca),
+            "ee" => Ok(CircuitRole::Ee),
+            _ => Err(format!("Unknown circuit role `{}`", name)),
+        }
+    }
+}
+
+/// The statements besides the certificate a circuit is chosen for by
+/// [`CircuitRegistry::select_for_der`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CircuitVariant {
+    pub anchor_set: bool,
+    pub revocation: bool,
+    pub attestation: bool,
+}
+
+/// A circuit as described by a manifest entry, which tells which certificates it can prove
+/// without loading its artifacts.
+#[derive(Debug, Clone)]
+pub struct CircuitDescriptor {
+    pub meta: CircuitMeta,
+    pub role: CircuitRole,
+    pub signature_algorithm: SignatureAlgorithm,
+    /// `MAX_TBS_LEN` of the circuit
+    pub max_tbs_len: usize,
+    pub max_extra_extension_len: usize,
+    pub max_extra_extension_count: usize,
+    /// Whether the circuit proves anchor-set membership instead of taking `prev_cmt`
+    pub anchor_set: bool,
+    /// Whether the circuit proves non-revocation against a `revocation_root`
+    pub revocation: bool,
+    /// Whether the end-entity circuit discloses attestation properties
+    pub attestation: bool,
+    /// Whether the end-entity circuit computes a `nym`
+    pub nym: bool,
+    /// Whether the end-entity circuit computes a `nullifier`
+    pub nullifier: bool,
+    /// Whether the end-entity circuit proves possession of the attested key
+    pub possession: bool,
+    /// Noir version the artifacts must have been compiled with, without build metadata
+    pub noir_version: Option<String>,
+}
+
+impl CircuitDescriptor {
+    /// Parses a manifest entry, resolving its artifact paths against `base_dir`. The SRS
+    /// path defaults to `srs`, the one shared by all the entries of the manifest.
+    fn from_manifest_entry(
+        entry: &Value,
+        base_dir: &Path,
+        srs: Option<&str>,
+    ) -> Result<Self, String> {
+        let id = manifest_str(entry, "id", "circuit")?;
+        let field = |key: &str| manifest_str(entry, key, id);
+        let limit = |key: &str| {
+            entry[key]
+                .as_u64()
+                .map(|v| v as usize)
+                .ok_or_else(|| format!("Circuit `{}` has no integer `{}`", id, key))
+        };
+        let srs = match entry["srs"].as_str().or(srs) {
+            Some(srs) => srs,
+            None => return Err(format!("Circuit `{}` has no `srs`", id)),
+        };
+
+        Ok(CircuitDescriptor {
+            meta: CircuitMeta {
+                id: id.to_string(),
+                circuit_path: resolve(base_dir, field("circuit")?),
+                verification_key_path: resolve(base_dir, field("verification_key")?),
+                srs_path: resolve(base_dir, srs),
+                circuit_bytes: None,
+                verification_key_bytes: None,
+                srs_bytes: None,
+            },
+            role: CircuitRole::from_name(field("role")?)?,
+            signature_algorithm: SignatureAlgorithm::from_name(field("signature_algorithm")?)?,
+            max_tbs_len: limit("max_tbs_len")?,
+            max_extra_extension_len: limit("max_extra_extension_len")?,
+            max_extra_extension_count: limit("max_extra_extension_count")?,
+            anchor_set: entry["anchor_set"].as_bool().unwrap_or(false),
+            revocation: entry["revocation"].as_bool().unwrap_or(false),
+            attestation: entry["attestation"].as_bool().unwrap_or(false),
+            nym: entry["nym"].as_bool().unwrap_or(false),
+            nullifier: entry["nullifier"].as_bool().unwrap_or(false),
+            possession: entry["possession"].as_bool().unwrap_or(false),
+            noir_version: entry["noir_version"].as_str().map(str::to_string),
+        })
+    }
+
+    pub fn fits(&self, parsed_cert: &ParsedCert) -> bool {
+        self.role == CircuitRole::of(parsed_cert)
+            && self.signature_algorithm == parsed_cert.signature_algorithm
+            && self.max_tbs_len >= parsed_cert.tbs_len as usize
+            && self.max_extra_extension_len >= parsed_cert.extra_extension_len as usize
+            && self.max_extra_extension_count >= parsed_cert.extra_extension_offsets.len()
+    }
+
+    /// Checks that the loaded `circuit` is the one described, so that a stale artifact fails
+    /// before proving rather than with an unsatisfied constraint.
+    pub(crate) fn check(&self, circuit: &Circuit) -> Result<(), String> {
+        if circuit.max_extra_extension_len != self.max_extra_extension_len
+            || circuit.max_extra_extension_count != self.max_extra_extension_count
+        {
+            return Err(format!(
+                "Circuit `{}` allows {} extra extensions of {} bytes, but its manifest says {} of {}",
+                self.meta.id,
+                circuit.max_extra_extension_count,
+                circuit.max_extra_extension_len,
+                self.max_extra_extension_count,
+                self.max_extra_extension_len
+            ));
+        }
+        let layout = circuit.public_inputs_layout;
+        for (setting, expected, actual) in [
+            ("anchor set", self.anchor_set, layout.anchor_set),
+            ("revocation", self.revocation, layout.revocation),
+            ("attestation", self.attestation, layout.attestation),
+            ("nym", self.nym, layout.nym),
+            ("nullifier", self.nullifier, layout.nullifier),
+            ("possession", self.possession, layout.possession),
+        ] {
+            if actual != expected {
+                return Err(format!(
+                    "Circuit `{}` does not match the {} setting of its manifest",
+                    self.meta.id, setting
+                ));
+            }
+        }
+        // CA circuits assert that the certificate may sign certificates and disclose `path_len`
+        if layout.path_len != (self.role == CircuitRole::Ca) {
+            return Err(format!(
+                "Circuit `{}` does not match the {:?} role of its manifest",
+                self.meta.id, self.role
+            ));
+        }
+        if let Some(expected) = &self.noir_version {
+            let version = circuit.noir_version.as_deref().unwrap_or_default();
+            // ignore the build metadata, i.e. the commit hash after `+`
+            if version.split('+').next() != Some(expected.as_str()) {
+                return Err(format!(
+                    "Circuit `{}` was compiled with Noir `{}`, but its manifest expects `{}`",
+                    self.meta.id, version, expected
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The variant the circuit is chosen for, or `None` if it computes a nym or nullifier or
+    /// proves possession, in which case it is proved by id
+    pub fn variant(&self) -> Option<CircuitVariant> {
+        if self.nym || self.nullifier || self.possession {
+            return None;
+        }
+        Some(CircuitVariant {
+            anchor_set: self.anchor_set,
+            revocation: self.revocation,
+            attestation: self.attestation,
+        })
+    }
+
+    /// Size of the circuit, for choosing the smallest one that fits
+    pub(crate) fn size(&self) -> (usize, usize, usize) {
+        (
+            self.max_tbs_len,
+            self.max_extra_extension_len,
+            self.max_extra_extension_count,
+        )
+    }
+}
+
+pub struct CircuitRegistry {
+    entries: Vec<CircuitDescriptor>,
+}
+
+impl CircuitRegistry {
+    pub fn new() -> Self {
+        CircuitRegistry {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Loads the manifest at `path`, as TOML if its extension is `.toml` and as JSON otherwise.
+    /// Relative artifact paths are resolved against the directory of the manifest.
+    pub fn from_manifest(path: impl AsRef<Path>) -> Result<Self, String> {
+        let mut registry = Self::new();
+        registry.load_manifest(path)?;
+        Ok(registry)
+    }
+
+    /// Loads `manifest.json` or else `manifest.toml` from `dir`.
+    pub fn from_dir(dir: impl AsRef<Path>) -> Result<Self, String> {
+        let dir = dir.as_ref();
+        let path = MANIFEST_FILE_NAMES
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|path| path.is_file())
+            .ok_or_else(|| format!("No circuit manifest found in {}", dir.display()))?;
+        Self::from_manifest(path)
+    }
+
+    pub fn load_manifest(&mut self, path: impl AsRef<Path>) -> Result<(), String> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read manifest {}: {}", path.display(), e))?;
+        let manifest: Value = if path.extension().is_some_and(|ext| ext == "toml") {
+            toml::from_str(&contents).map_err(|e| e.to_string())
+        } else {
+            serde_json::from_str(&contents).map_err(|e| e.to_string())
+        }
+        .map_err(|e| format!("Failed to parse manifest {}: {}", path.display(), e))?;
+
+        let base_dir = path.parent().unwrap_or(Path::new(""));
+        for descriptor in parse_manifest(&manifest, base_dir)? {
+            self.register(descriptor)?;
+        }
+        Ok(())
+    }
+
+    pub fn register(&mut self, descriptor: CircuitDescriptor) -> Result<(), String> {
+        if self.get(&descriptor.meta.id).is_some() {
+            return Err(format!(
+                "Circuit `{}` is already registered",
+                descriptor.meta.id
+            ));
+        }
+        self.entries.push(descriptor);
+        Ok(())
+    }
+
+    pub fn get(&self, id: &str) -> Option<&CircuitMeta> {
+        self.descriptor(id).map(|descriptor| &descriptor.meta)
+    }
+
+    pub fn descriptor(&self, id: &str) -> Option<&CircuitDescriptor> {
+        self.entries
+            .iter()
+            .find(|descriptor| descriptor.meta.id == id)
+    }
+
+    pub fn descriptors(&self) -> impl Iterator<Item = &CircuitDescriptor> {
+        self.entries.iter()
+    }
+
+    /// Chooses the circuit for the DER-encoded certificate `cert`, as [`crate::prove_chain`]
+    /// does, without loading any artifact.
+    pub fn select_for_der(
+        &self,
+        cert: &[u8],
+        variant: CircuitVariant,
+    ) -> Result<&CircuitDescriptor, String> {
+        self.select_descriptor(&ParsedCert::from_der(cert)?, variant)
+    }
+
+    /// Returns the smallest circuit of `variant` that fits `parsed_cert`.
+    pub(crate) fn select_descriptor(
+        &self,
+        parsed_cert: &ParsedCert,
+        variant: CircuitVariant,
+    ) -> Result<&CircuitDescriptor, String> {
+        self.entries
+            .iter()
+            .filter(|descriptor| {
+                descriptor.variant() == Some(variant) && descriptor.fits(parsed_cert)
+            })
+            .min_by_key(|descriptor| descriptor.size())
+            .ok_or_else(|| {
+                format!(
+                    "no {:?} {:?} circuit{}{}{} fits a TBSCertificate of {} bytes with {} extra extensions of {} bytes",
+                    parsed_cert.signature_algorithm,
+                    CircuitRole::of(parsed_cert),
+                    if variant.anchor_set {
+                        " with an anchor set"
+                    } else {
+                        ""
+                    },
+                    if variant.revocation {
+                        " proving non-revocation"
+                    } else {
+                        ""
+                    },
+                    if variant.attestation {
+                        " disclosing attestation properties"
+                    } else {
+                        ""
+                    },
+                    parsed_cert.tbs_len,
+                    parsed_cert.extra_extension_offsets.len(),
+                    parsed_cert.extra_extension_len
+                )
+            })
+    }
+
+    /// Loads the circuit chosen by [`Self::select_descriptor`] and checks it against its
+    /// manifest entry.
+    pub(crate) fn select(
+        &self,
+        parsed_cert: &ParsedCert,
+        variant: CircuitVariant,
+    ) -> Result<Circuit, String> {
+        let descriptor = self.select_descriptor(parsed_cert, variant)?;
+        let circuit = Circuit::new(&descriptor.meta)?;
+        descriptor.check(&circuit)?;
+        Ok(circuit)
+    }
+}
+
+impl Default for CircuitRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parses the `circuits` of a manifest, whose top-level `srs` is shared by all of them.
+fn parse_manifest(manifest: &Value, base_dir: &Path) -> Result<Vec<CircuitDescriptor>, String> {
+    let srs = manifest["srs"].as_str();
+    manifest["circuits"]
+        .as_array()
+        .ok_or("Manifest has no `circuits` array")?
+        .iter()
+        .map(|entry| CircuitDescriptor::from_manifest_entry(entry, base_dir, srs))
+        .collect()
+}
+
+fn manifest_str<'a>(entry: &'a Value, key: &str, id: &str) -> Result<&'a str, String> {
+    entry[key]
+        .as_str()
+        .ok_or_else(|| format!("Circuit `{}` has no string `{}`", id, key))
+}
+
+fn resolve(base_dir: &Path, path: &str) -> String {
+    let path: PathBuf = base_dir.join(path);
+    path.to_string_lossy().into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST_TOML: &str = r#"
+        srs = "common.srs"
+
+        [[circuits]]
+        id = "es256_ee"
+        role = "ee"
+        signature_algorithm = "ES256"
+        max_tbs_len = 700
+        max_extra_extension_len = 300
+        max_extra_extension_count = 4
+        circuit = "es256_ee.json"
+        verification_key = "es256_ee.vk"
+
+        [[circuits]]
+        id = "es256_ee_small"
+        role = "EE"
+        signature_algorithm = "ES256"
+        max_tbs_len = 700
+        max_extra_extension_len = 10
+        max_extra_extension_count = 4
+        circuit = "es256_ee_small.json"
+        verification_key = "es256_ee_small.vk"
+        srs = "/srs/small.srs"
+    "#;
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest: Value = toml::from_str(MANIFEST_TOML).unwrap();
+        let descriptors = parse_manifest(&manifest, Path::new("artifacts")).unwrap();
+        assert_eq!(descriptors.len(), 2);
+        assert_eq!(descriptors[0].role, CircuitRole::Ee);
+        assert_eq!(
+            descriptors[0].signature_algorithm,
+            SignatureAlgorithm::Es256
+        );
+        assert_eq!(
+            descriptors[0].meta.circuit_path,
+            Path::new("artifacts/es256_ee.json").to_string_lossy()
+        );
+        assert_eq!(
+            descriptors[0].meta.srs_path,
+            Path::new("artifacts/common.srs").to_string_lossy()
+        );
+        assert_eq!(descriptors[1].meta.srs_path, "/srs/small.srs");
+        assert!(!descriptors[1].anchor_set);
+        assert!(!descriptors[1].revocation);
+        assert_eq!(descriptors[1].variant(), Some(CircuitVariant::default()));
+        assert_eq!(descriptors[1].noir_version, None);
+
+        let mut manifest = manifest;
+        manifest["circuits"][0]["signature_algorithm"] = "RS256".into();
+        assert!(parse_manifest(&manifest, Path::new("")).is_err());
+        manifest["circuits"][0]["signature_algorithm"] = "ES256".into();
+        manifest["circuits"][0]
+            .as_object_mut()
+            .unwrap()
+            .remove("max_tbs_len");
+        assert!(parse_manifest(&manifest, Path::new("")).is_err());
+    }
+
+    /// The circuits of `data` plus manifest entries for the variants of es256_ca and es256_ee,
+    /// whose artifacts are not needed to select them
+    fn registry_with_variants() -> CircuitRegistry {
+        let mut manifest: Value =
+            serde_json::from_str(include_str!("../data/manifest.json")).unwrap();
+        let circuits = manifest["circuits"].as_array_mut().unwrap();
+        let entry = |id: &str| circuits.iter().find(|c| c["id"] == id).unwrap().clone();
+        let mut variants = Vec::new();
+        for (base, id, setting) in [
+            ("es256_ca", "es256_ca_revocation", "revocation"),
+            ("es256_ee", "es256_ee_revocation", "revocation"),
+            ("es256_ca", "es256_ca_anchor_set", "anchor_set"),
+            ("es256_ee", "es256_ee_attested", "attestation"),
+            ("es256_ee", "es256_ee_nym", "nym"),
+            ("es256_ee", "es256_ee_nullifier", "nullifier"),
+            ("es256_ee", "es256_ee_pop", "possession"),
+        ] {
+            let mut variant = entry(base);
+            variant["id"] = id.into();
+            variant[setting] = true.into();
+            variants.push(variant);
+        }
+        circuits.extend(variants);
+
+        let mut registry = CircuitRegistry::new();
+        for descriptor in parse_manifest(&manifest, Path::new("data")).unwrap() {
+            registry.register(descriptor).unwrap();
+        }
+        registry
+    }
+
+    #[test]
+    fn test_select_descriptor() {
+        let registry = registry_with_variants();
+        assert!(registry.get("es256_ee_long_ext").is_some());
+
+        let plain = CircuitVariant::default();
+        let revocation = CircuitVariant {
+            revocation: true,
+            ..plain
+        };
+        let anchor_set = CircuitVariant {
+            anchor_set: true,
+            ..plain
+        };
+        let attestation = CircuitVariant {
+            attestation: true,
+            ..plain
+        };
+
+        let ca = include_bytes!("../test_data/es256_ca.der");
+        let select_ca = |variant| registry.select_for_der(ca, variant).map(|d| &d.meta.id);
+        assert_eq!(select_ca(plain).unwrap(), "es256_ca");
+        assert_eq!(select_ca(revocation).unwrap(), "es256_ca_revocation");
+        assert_eq!(select_ca(anchor_set).unwrap(), "es256_ca_anchor_set");
+        assert!(
+            select_ca(CircuitVariant {
+                anchor_set: true,
+                revocation: true,
+                ..plain
+            })
+            .is_err()
+        );
+        assert!(select_ca(attestation).is_err());
+
+        let ee = ParsedCert::from_der(include_bytes!("../test_data/es256_ee.der")).unwrap();
+        let select_ee = |variant| registry.select_descriptor(&ee, variant).map(|d| &d.meta.id);
+        assert_eq!(select_ee(revocation).unwrap(), "es256_ee_revocation");
+        assert_eq!(select_ee(attestation).unwrap(), "es256_ee_attested");
+        let descriptor = registry.select_descriptor(&ee, plain).unwrap();
+        assert_eq!(descriptor.meta.id, "es256_ee");
+        assert!(descriptor.fits(&ee));
+        // the nym, nullifier and possession variants fit as well, but are proved by id
+        for id in ["es256_ee_nym", "es256_ee_nullifier", "es256_ee_pop"] {
+            let variant = registry.descriptor(id).unwrap();
+            assert!(variant.fits(&ee));
+            assert_eq!(variant.variant(), None);
+        }
+        assert!(
+            registry
+                .descriptors()
+                .filter(|d| d.fits(&ee))
+                .all(|d| d.size() >= descriptor.size())
+        );
+
+        // a certificate that outgrows every circuit has none
+        let mut long = ee;
+        long.tbs_len = 701;
+        assert!(registry.select_descriptor(&long, plain).is_err());
+    }
+
+    #[test]
+    fn test_register_twice() {
+        let registry = CircuitRegistry::from_dir("data").unwrap();
+        let mut copy = CircuitRegistry::new();
+        let descriptor = registry.descriptor("es256_ca").unwrap().clone();
+        copy.register(descriptor.clone()).unwrap();
+        assert!(copy.register(descriptor).is_err());
+    }
+}
@@ -0,0 +1,256 @@
+//! Non-revocation of certificate serial numbers against the Android attestation status list.
+//!
+//! The revoked serial numbers, shifted up by one, are sorted and framed by 0 and the largest
+//! field element, and each leaf of a Poseidon Merkle tree commits to a range `(low, high)` of
+//! two adjacent ones.
+//! A serial number is not revoked iff it lies strictly inside the range of some leaf, so a
+//! single Merkle path proves non-membership. The tree is padded with empty `(max, max)` ranges.
+
+use std::collections::BTreeSet;
+
+use ark_bn254::Fr;
+use ark_crypto_primitives::crh::CRHScheme;
+use ark_ff::{AdditiveGroup, Field, PrimeField};
+use num_bigint::BigUint;
+use serde_json::Value;
+
+use crate::{
+    merkle::MerkleTree,
+    poseidon::CRH,
+    utils::{POSEIDON_CONFIG_2, field_to_hex},
+};
+
+/// Revoked certificate serial numbers, as unsigned big-endian integers.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RevocationList {
+    serials: BTreeSet<Fr>,
+}
+
+impl RevocationList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, serial_number: &[u8]) {
+        self.serials.insert(serial_to_field(serial_number));
+    }
+
+    /// Parses an attestation status list, listing every entry as revoked whatever its status.
+    /// Its `entries` are keyed by serial numbers in hex.
+    pub fn from_json(json: &[u8]) -> Result<Self, String> {
+        let v: Value = serde_json::from_slice(json)
+            .map_err(|e| format!("Failed to parse status list: {}", e))?;
+        let entries = v["entries"]
+            .as_object()
+            .ok_or("Status list has no `entries` object")?;
+
+        let mut list = Self::new();
+        for serial in entries.keys() {
+            let serial = BigUint::parse_bytes(serial.as_bytes(), 16)
+                .ok_or_else(|| format!("Invalid serial number `{}` in status list", serial))?;
+            list.add(&serial.to_bytes_be());
+        }
+        Ok(list)
+    }
+
+    pub fn len(&self) -> usize {
+        self.serials.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.serials.is_empty()
+    }
+
+    pub fn is_revoked(&self, serial_number: &[u8]) -> bool {
+        self.serials.contains(&serial_to_field(serial_number))
+    }
+}
+
+/// Witness that a serial number is not revoked: the range leaf at `index` and its Merkle path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NonRevocationWitness {
+    pub low: Fr,
+    pub high: Fr,
+    pub index: u64,
+    /// Siblings from the leaf up to the root
+    pub path: Vec<Fr>,
+}
+
+pub struct RevocationTree {
+    bounds: Vec<Fr>,
+    tree: MerkleTree,
+}
+
+impl RevocationTree {
+    /// Builds the tree of `list` with `depth` levels, matching the `revocation_path` length of
+    /// the circuits.
+    pub fn new(list: &RevocationList, depth: usize) -> Result<Self, String> {
+        let max = -Fr::ONE;
+        let mut bounds = Vec::with_capacity(list.len() + 2);
+        bounds.push(Fr::ZERO);
+        bounds.extend(list.serials.iter().copied());
+        bounds.push(max);
+
+        let leaves = bounds
+            .windows(2)
+            .map(|range| range_leaf(range[0], range[1]))
+            .collect::<Result<Vec<_>, _>>()?;
+        let tree = MerkleTree::new(leaves, range_leaf(max, max)?, depth)
+            .map_err(|e| format!("{} revoked serial numbers do not fit: {}", list.len(), e))?;
+
+        Ok(RevocationTree { bounds, tree })
+    }
+
+    pub fn root(&self) -> Fr {
+        self.tree.root()
+    }
+
+    pub fn depth(&self) -> usize {
+        self.tree.depth()
+    }
+
+    /// Witness that `serial_number` is not revoked, failing if it is.
+    pub fn non_revocation_witness(
+        &self,
+        serial_number: &[u8],
+    ) -> Result<NonRevocationWitness, String> {
+        let serial = serial_to_field(serial_number);
+        // number of bounds at most `serial`, so that `serial` lies in the range before it
+        let index = self.bounds.partition_point(|bound| *bound <= serial) - 1;
+        let (low, high) = (self.bounds[index], self.bounds[index + 1]);
+        if low == serial {
+            return Err(format!(
+                "Serial number {} is revoked",
+                hex::encode(serial_number)
+            ));
+        }
+
+        Ok(NonRevocationWitness {
+            low,
+            high,
+            index: index as u64,
+            path: self.tree.path(index)?,
+        })
+    }
+}
+
+impl std::fmt::Debug for RevocationTree {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RevocationTree")
+            .field("revoked", &(self.bounds.len() - 2))
+            .field("root", &field_to_hex(&self.root()))
+            .finish()
+    }
+}
+
+/// Leaf of the range `(low, high)`, matching `hash_2([low, high])` in the circuit
+pub(crate) fn range_leaf(low: Fr, high: Fr) -> Result<Fr, String> {
+    CRH::<Fr>::evaluate(&*POSEIDON_CONFIG_2, [low, high])
+        .map_err(|e| format!("Poseidon CRH error: {}", e))
+}
+
+/// Serial number as the unsigned big-endian integer of its content octets plus one, like
+/// `revocation::serial_to_field` in the circuit. The shift keeps serial number 0 above the lower
+/// bound 0 of the tree; serial numbers have at most 20 octets, so it never wraps around.
+pub(crate) fn serial_to_field(serial_number: &[u8]) -> Fr {
+    Fr::from_be_bytes_mod_order(serial_number) + Fr::ONE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{merkle::root_from_path, utils::hex_to_field};
+
+    fn root_of(witness: &NonRevocationWitness) -> Fr {
+        let leaf = range_leaf(witness.low, witness.high).unwrap();
+        root_from_path(leaf, witness.index, &witness.path).unwrap()
+    }
+
+    #[test]
+    fn test_revocation_list_from_json() {
+        let list = RevocationList::from_json(
+            br#"{"entries": {
+                "2c8cdddfd5e03bfc": {"status": "REVOKED", "reason": "KEY_COMPROMISE"},
+                "c8966fcb2fbb0d7a": {"status": "SUSPENDED"},
+                "1": {"status": "REVOKED"}
+            }}"#,
+        )
+        .unwrap();
+        assert_eq!(list.len(), 3);
+        assert!(list.is_revoked(&hex::decode("2c8cdddfd5e03bfc").unwrap()));
+        assert!(list.is_revoked(&[0x00, 0x01])); // DER content octets with a leading zero
+        assert!(!list.is_revoked(&[0x02]));
+
+        assert!(RevocationList::from_json(br#"{"entries": {"xyz": {}}}"#).is_err());
+        assert!(RevocationList::from_json(br#"{}"#).is_err());
+    }
+
+    #[test]
+    fn test_non_revocation_witness() {
+        let mut list = RevocationList::new();
+        for serial in [[0x10], [0x30], [0x20]] {
+            list.add(&serial);
+        }
+        let tree = RevocationTree::new(&list, 3).unwrap();
+
+        let witness = tree.non_revocation_witness(&[0x25]).unwrap();
+        assert_eq!(
+            (witness.low, witness.high),
+            (Fr::from(0x21), Fr::from(0x31))
+        );
+        assert_eq!(witness.index, 2);
+        assert_eq!(witness.path.len(), 3);
+        assert_eq!(root_of(&witness), tree.root());
+        // computed with ark-crypto-primitives' MerkleTree over the same CRH and TwoToOneCRH
+        assert_eq!(
+            field_to_hex(&tree.root()),
+            "1992f4fe5754eba3af036aac1a94283306a6ec0635e19ee7a2ec5e5896c0898b"
+        );
+        assert_eq!(
+            witness.path[2],
+            hex_to_field("1a8de4467b278120bd48c1e87c680896f7258ff1e1af866b0ecc9966c9589029")
+                .unwrap()
+        );
+
+        let witness = tree.non_revocation_witness(&[0x01]).unwrap();
+        assert_eq!((witness.low, witness.high), (Fr::ZERO, Fr::from(0x11)));
+        assert_eq!(witness.index, 0);
+        assert_eq!(root_of(&witness), tree.root());
+
+        // serial number 0 lies above the lower bound 0 of the first range
+        let witness = tree.non_revocation_witness(&[0x00]).unwrap();
+        assert_eq!((witness.low, witness.high), (Fr::ZERO, Fr::from(0x11)));
+        assert_eq!(root_of(&witness), tree.root());
+
+        let witness = tree.non_revocation_witness(&[0xff; 20]).unwrap();
+        assert_eq!((witness.low, witness.high), (Fr::from(0x31), -Fr::ONE));
+        assert_eq!(witness.index, 3);
+        assert_eq!(root_of(&witness), tree.root());
+
+        assert!(tree.non_revocation_witness(&[0x20]).is_err());
+        assert!(tree.non_revocation_witness(&[0x00, 0x30]).is_err());
+
+        list.add(&[0x00]);
+        let tree = RevocationTree::new(&list, 3).unwrap();
+        assert!(tree.non_revocation_witness(&[0x00]).is_err());
+        assert_eq!(
+            tree.non_revocation_witness(&[0x01]).unwrap().low,
+            Fr::from(0x01)
+        );
+    }
+
+    #[test]
+    fn test_revocation_tree_capacity() {
+        // n revoked serial numbers make n + 1 ranges
+        let mut list = RevocationList::new();
+        list.add(&[0x01]);
+        assert!(RevocationTree::new(&list, 1).is_ok());
+        list.add(&[0x02]);
+        assert!(RevocationTree::new(&list, 1).is_err());
+        assert!(RevocationTree::new(&list, 0).is_err());
+
+        // an empty list still commits to a root, which differs from that of a non-empty one
+        let empty = RevocationTree::new(&RevocationList::new(), 2).unwrap();
+        assert_ne!(empty.root(), RevocationTree::new(&list, 2).unwrap().root());
+    }
+}
@@ -4,6 +4,7 @@ use ark_bn254::Fr;
 use ark_crypto_primitives::{crh::CRHScheme, sponge::poseidon::PoseidonConfig};
 use ark_ff::{AdditiveGroup, BigInteger, PrimeField};
 use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Datelike, TimeDelta, TimeZone, Timelike, Utc};
 
 use crate::poseidon::{CRH, get_poseidon_parameters_2};
 
@@ -46,6 +47,41 @@ pub struct UtcTime {
 }
 
 impl UtcTime {
+    pub fn from_datetime(datetime: &DateTime<Utc>) -> Self {
+        UtcTime {
+            year: datetime.year() as u16,
+            month: datetime.month() as u8,
+            day: datetime.day() as u8,
+            hour: datetime.hour() as u8,
+            minute: datetime.minute() as u8,
+            second: datetime.second() as u8,
+        }
+    }
+
+    pub fn from_bytes(bytes: [u8; 7]) -> Self {
+        UtcTime {
+            year: u16::from_be_bytes([bytes[0], bytes[1]]),
+            month: bytes[2],
+            day: bytes[3],
+            hour: bytes[4],
+            minute: bytes[5],
+            second: bytes[6],
+        }
+    }
+
+    pub fn to_datetime(self) -> Result<DateTime<Utc>, String> {
+        Utc.with_ymd_and_hms(
+            self.year as i32,
+            self.month as u32,
+            self.day as u32,
+            self.hour as u32,
+            self.minute as u32,
+            self.second as u32,
+        )
+        .single()
+        .ok_or_else(|| format!("{:?} is not a valid datetime", self))
+    }
+
     pub fn to_bytes(&self) -> [u8; 7] {
         let mut bytes = [0u8; 7];
         bytes[0..2].copy_from_slice(&self.year.to_be_bytes());
@@ -58,6 +94,56 @@ impl UtcTime {
     }
 }
 
+/// Granularity of the `now` a proof discloses. A coarse `now` is the start of the period
+/// containing the proving time, and the whole period must lie within the validity of each
+/// certificate.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TimeGranularity {
+    #[default]
+    Second = 0,
+    Minute = 1,
+    Hour = 2,
+    Day = 3,
+}
+
+impl TimeGranularity {
+    pub fn from_u64(v: u64) -> Result<Self, String> {
+        match v {
+            0 => Ok(TimeGranularity::Second),
+            1 => Ok(TimeGranularity::Minute),
+            2 => Ok(TimeGranularity::Hour),
+            3 => Ok(TimeGranularity::Day),
+            _ => Err(format!("Unknown TimeGranularity {}", v)),
+        }
+    }
+
+    /// Start of the period containing `datetime`
+    pub fn truncate(&self, datetime: &DateTime<Utc>) -> DateTime<Utc> {
+        let mut time = UtcTime::from_datetime(datetime);
+        if *self >= TimeGranularity::Minute {
+            time.second = 0;
+        }
+        if *self >= TimeGranularity::Hour {
+            time.minute = 0;
+        }
+        if *self >= TimeGranularity::Day {
+            time.hour = 0;
+        }
+        time.to_datetime().unwrap()
+    }
+
+    /// Last second of the period starting at `start`, matching `UtcTime::period_end` in the circuit
+    pub fn period_end(&self, start: &DateTime<Utc>) -> DateTime<Utc> {
+        let len = match self {
+            TimeGranularity::Second => TimeDelta::seconds(1),
+            TimeGranularity::Minute => TimeDelta::minutes(1),
+            TimeGranularity::Hour => TimeDelta::hours(1),
+            TimeGranularity::Day => TimeDelta::days(1),
+        };
+        *start + len - TimeDelta::seconds(1)
+    }
+}
+
 pub fn to_fixed_array<const N: usize>(src: &[u8]) -> Result<[u8; N], String> {
     if src.len() > N {
         return Err(format!("input length {} exceeds {} bytes", src.len(), N));
@@ -134,6 +220,74 @@ pub fn commit_attrs(
     Ok(state)
 }
 
+pub const MAX_PACKAGE_NAME_LEN: usize = 124;
+
+/// Hash of an attesting app's identity, matching `hash_app_id` in the circuit: starting from
+/// the package name length, absorbs the zero-padded package name and the signing certificate
+/// digest in 31-byte little-endian chunks.
+pub fn app_id_hash(package_name: &[u8], signature_digest: &[u8]) -> Result<Fr, String> {
+    let name_len = package_name.len();
+    let package_name: [u8; MAX_PACKAGE_NAME_LEN] =
+        to_fixed_array(package_name).map_err(|e| format!("package name too long: {}", e))?;
+    let signature_digest: [u8; 32] = signature_digest
+        .try_into()
+        .map_err(|_| "signature digest must be 32 bytes")?;
+
+    let hash = |state: Fr, chunk: &[u8]| {
+        CRH::<Fr>::evaluate(
+            &*POSEIDON_CONFIG_2,
+            [state, Fr::from_le_bytes_mod_order(chunk)],
+        )
+        .map_err(|e| format!("Poseidon CRH error: {}", e))
+    };
+
+    let mut state = Fr::from(name_len as u64);
+    for chunk in package_name.chunks(31) {
+        state = hash(state, chunk)?;
+    }
+    state = hash(state, &signature_digest[..31])?;
+    hash(state, &signature_digest[31..])
+}
+
+/// Context-specific pseudonym of a subject public key, matching `nym::nym` in the circuit.
+pub fn nym(pk_x: [u8; 32], pk_y: [u8; 32], ctx: Fr, user_secret: Fr) -> Result<Fr, String> {
+    let state = hash_2(hash_public_key(pk_x, pk_y)?, ctx)?;
+    hash_2(state, user_secret)
+}
+
+/// Nullifier of a subject public key for `scope` and `epoch`, matching `nym::nullifier` in the
+/// circuit. Without `secret`, it cannot be linked to the public key.
+pub fn nullifier(
+    pk_x: [u8; 32],
+    pk_y: [u8; 32],
+    scope: Fr,
+    epoch: u64,
+    secret: Fr,
+) -> Result<Fr, String> {
+    let mut state = hash_2(hash_public_key(pk_x, pk_y)?, scope)?;
+    state = hash_2(state, Fr::from(epoch))?;
+    hash_2(state, secret)
+}
+
+// Absorbs the public key in 31-byte little-endian chunks
+fn hash_public_key(pk_x: [u8; 32], pk_y: [u8; 32]) -> Result<Fr, String> {
+    let mut pk = [0u8; 64];
+    pk[..32].copy_from_slice(&pk_x);
+    pk[32..].copy_from_slice(&pk_y);
+    let mut chunks = pk.chunks(31).map(Fr::from_le_bytes_mod_order);
+
+    let mut state = chunks.next().unwrap();
+    for chunk in chunks {
+        state = hash_2(state, chunk)?;
+    }
+    Ok(state)
+}
+
+fn hash_2(left: Fr, right: Fr) -> Result<Fr, String> {
+    CRH::<Fr>::evaluate(&*POSEIDON_CONFIG_2, [left, right])
+        .map_err(|e| format!("Poseidon CRH error: {}", e))
+}
+
 pub fn from_u8_array_to_fr_vec(u8_array: &[u8]) -> Vec<Fr> {
     u8_array.iter().map(|b| Fr::from(*b as u64)).collect()
 }
@@ -229,4 +383,78 @@ mod tests {
             "2a296b0c9a2c8b4c6c56357c632860849f42a4defa6b491b2421a962a3543f5c"
         );
     }
+
+    #[test]
+    fn test_app_id_hash() {
+        let digest =
+            hex::decode("a6bfe8e8029aff3be388beb06371ccdf94f801df433d76b7cbedcf4b538dd876")
+                .unwrap();
+        let hash = app_id_hash(b"com.example.moproapp", &digest).unwrap();
+        assert_eq!(
+            field_to_hex(&hash),
+            "082fa0ba5ca89c1918e3cedccde7bb5968fab935d664c3e8ba68b267b995de74"
+        );
+        assert_ne!(
+            app_id_hash(b"com.example.moproapp\0", &digest).unwrap(),
+            hash
+        );
+        assert!(app_id_hash(&[b'a'; MAX_PACKAGE_NAME_LEN + 1], &digest).is_err());
+        assert!(app_id_hash(b"com.example.moproapp", &digest[..31]).is_err());
+    }
+
+    #[test]
+    fn test_nym() {
+        let cert = include_bytes!("../test_data/es256_ee.der");
+        let parsed_cert = crate::cert::ParsedCert::from_der(cert).unwrap();
+        let pk_x = parsed_cert.subject_pk_x;
+        let pk_y = parsed_cert.subject_pk_y;
+        let secret = Fr::from(0xcafeu64);
+        let nym_1 = nym(pk_x, pk_y, Fr::from(1u64), secret).unwrap();
+        assert_eq!(
+            field_to_hex(&nym_1),
+            "169cf2496642b471ab008314ab38b600845185c0527591850a73ab158cac17fe"
+        );
+        assert_ne!(nym(pk_x, pk_y, Fr::from(2u64), secret).unwrap(), nym_1);
+        assert_ne!(
+            nym(pk_x, pk_y, Fr::from(1u64), Fr::from(0xcaffu64)).unwrap(),
+            nym_1
+        );
+    }
+
+    #[test]
+    fn test_nullifier() {
+        let cert = include_bytes!("../test_data/es256_ee.der");
+        let parsed_cert = crate::cert::ParsedCert::from_der(cert).unwrap();
+        let pk_x = parsed_cert.subject_pk_x;
+        let pk_y = parsed_cert.subject_pk_y;
+        let secret = Fr::from(0xcafeu64);
+        let nullifier_1 = nullifier(pk_x, pk_y, Fr::from(1u64), 20250901, secret).unwrap();
+        assert_eq!(
+            field_to_hex(&nullifier_1),
+            "0d5cfbaf71d4d08bb3df03768418852e36eca2d719bde2ed9674814b951c8ed4"
+        );
+        let nullifier_2 = nullifier(pk_x, pk_y, Fr::from(1u64), 20250902, secret).unwrap();
+        assert_ne!(nullifier_1, nullifier_2);
+        let other_secret = nullifier(pk_x, pk_y, Fr::from(1u64), 20250901, Fr::from(0xbeefu64));
+        assert_ne!(nullifier_1, other_secret.unwrap());
+    }
+
+    #[test]
+    fn test_time_granularity() {
+        let now = Utc.with_ymd_and_hms(2025, 9, 14, 13, 18, 39).unwrap();
+        let hour = TimeGranularity::Hour.truncate(&now);
+        assert_eq!(hour, Utc.with_ymd_and_hms(2025, 9, 14, 13, 0, 0).unwrap());
+        assert_eq!(
+            TimeGranularity::Hour.period_end(&hour),
+            Utc.with_ymd_and_hms(2025, 9, 14, 13, 59, 59).unwrap()
+        );
+        let day = TimeGranularity::Day.truncate(&now);
+        assert_eq!(
+            TimeGranularity::Day.period_end(&day),
+            Utc.with_ymd_and_hms(2025, 9, 14, 23, 59, 59).unwrap()
+        );
+        assert_eq!(TimeGranularity::Second.truncate(&now), now);
+        assert_eq!(TimeGranularity::Second.period_end(&now), now);
+        assert!(TimeGranularity::from_u64(4).is_err());
+    }
 }
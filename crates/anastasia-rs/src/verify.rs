@@ -0,0 +1,17 @@
+use crate::{
+    circuit::Circuit,
+    public_inputs::{PublicInputs, split_proof_with_layout},
+};
+
+use noir::barretenberg::verify::verify_ultra_honk_keccak;
+
+pub fn verify(circuit: &Circuit, proof: Vec<u8>) -> Result<PublicInputs, String> {
+    let (public_inputs, _) = split_proof_with_layout(&proof, circuit.public_inputs_layout)?;
+
+    let valid = verify_ultra_honk_keccak(proof, circuit.verification_key.clone(), false)?;
+    if !valid {
+        return Err("Proof verification failed".to_string());
+    }
+
+    Ok(public_inputs)
+}